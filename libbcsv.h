@@ -17,9 +17,33 @@ struct PtrInfo {
     ~PtrInfo() { free_PtrInfo(*this); }
 };
 
-PtrInfo bcsv_to_csv(const char*, const uint8_t*, size_t, uint8_t);
+#define BCSV_ERR_NULL -1
+#define BCSV_ERR_READ -2
+#define BCSV_ERR_RANGE -3
+#define BCSV_ERR_CONVERT -4
+
+const char* bcsv_last_error();
+
+struct Header {
+    uint32_t entrycount;
+    uint32_t fieldcount;
+    uint32_t entrydataoff;
+    uint32_t entrysize;
+};
+
+int32_t bcsv_read_header(const uint8_t*, size_t, uint8_t, Header*);
+int64_t bcsv_field_count(const uint8_t*, size_t, uint8_t);
+
+struct BcsvHandle;
+
+BcsvHandle* bcsv_open(const uint8_t*, size_t, uint8_t, const char*);
+void bcsv_close(BcsvHandle*);
+int64_t bcsv_get_field_count(const BcsvHandle*);
+int64_t bcsv_get_field(const BcsvHandle*, size_t, uint32_t*, uint8_t*, char*, size_t);
+// A NULL hash path names fields by hash; an unreadable hash file is an error.
+PtrInfo bcsv_to_csv(const char*, const uint8_t*, size_t, size_t, uint8_t);
 PtrInfo csv_to_bcsv(const char*, uint8_t);
-void bcsv_to_xlsx(const char*, const char*, const uint8_t*, size_t, uint8_t);
+int32_t bcsv_to_xlsx(const char*, const char*, const uint8_t*, size_t, size_t, uint8_t);
 
 #ifdef __cplusplus
 }
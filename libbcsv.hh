@@ -3,6 +3,7 @@
 #error libbcsv.hh must be included in a C++ file!
 #endif
 #pragma once
+#include <cstddef>
 #include <cstdint>
 #include <memory>
 #include <string>
@@ -10,25 +11,29 @@
 
 /// DLL Exports
 extern "C" {
-std::vector<std::uint8_t> *libbcsv$cxxbridge1$bcsv_to_csv(std::string const &path, std::vector<std::uint8_t> const &data, std::uint8_t endian) noexcept;
+std::vector<std::uint8_t> *libbcsv$cxxbridge1$bcsv_to_csv(std::string const &path, std::vector<std::uint8_t> const &data, std::size_t offset, std::uint8_t endian) noexcept;
 
 std::vector<std::uint8_t> *libbcsv$cxxbridge1$csv_to_bcsv(std::string const &path, std::uint8_t endian) noexcept;
 
-void libbcsv$cxxbridge1$bcsv_to_xlsx(std::string const &path, std::string const &output, std::vector<std::uint8_t> const &data, std::uint8_t endian) noexcept;
+bool libbcsv$cxxbridge1$bcsv_to_xlsx(std::string const &path, std::string const &output, std::vector<std::uint8_t> const &data, std::size_t offset, std::uint8_t endian) noexcept;
+
+std::vector<std::uint8_t> *libbcsv$cxxbridge1$bcsv_last_error() noexcept;
 } // extern "C"
 
 namespace libbcsv {
-std::unique_ptr<std::vector<std::uint8_t>> bcsv_to_csv(std::string const &path, std::vector<std::uint8_t> const &data, std::uint8_t endian) noexcept;
+std::unique_ptr<std::vector<std::uint8_t>> bcsv_to_csv(std::string const &path, std::vector<std::uint8_t> const &data, std::size_t offset, std::uint8_t endian) noexcept;
 
 std::unique_ptr<std::vector<std::uint8_t>> csv_to_bcsv(std::string const &path, std::uint8_t endian) noexcept;
 
-void bcsv_to_xlsx(std::string const &path, std::string const &output, std::vector<std::uint8_t> const &data, std::uint8_t endian) noexcept;
+bool bcsv_to_xlsx(std::string const &path, std::string const &output, std::vector<std::uint8_t> const &data, std::size_t offset, std::uint8_t endian) noexcept;
+
+std::unique_ptr<std::vector<std::uint8_t>> bcsv_last_error() noexcept;
 } // namespace libbcsv
 
 std::unique_ptr<std::vector<std::uint8_t>> 
-libbcsv::bcsv_to_csv(const std::string& path, const std::vector<std::uint8_t>& data, std::uint8_t endian) 
+libbcsv::bcsv_to_csv(const std::string& path, const std::vector<std::uint8_t>& data, std::size_t offset, std::uint8_t endian) 
 noexcept {
-    return std::unique_ptr<std::vector<std::uint8_t>>(libbcsv$cxxbridge1$bcsv_to_csv(path, data, endian));
+    return std::unique_ptr<std::vector<std::uint8_t>>(libbcsv$cxxbridge1$bcsv_to_csv(path, data, offset, endian));
 }
 
 std::unique_ptr<std::vector<std::uint8_t>>
@@ -36,6 +41,10 @@ libbcsv::csv_to_bcsv(const std::string& path, std::uint8_t endian) noexcept {
     return std::unique_ptr<std::vector<std::uint8_t>>(libbcsv$cxxbridge1$csv_to_bcsv(path, endian));
 }
 
-void libbcsv::bcsv_to_xlsx(const std::string& path, const std::string& output, const std::vector<std::uint8_t>& data, std::uint8_t endian) noexcept {
-    libbcsv$cxxbridge1$bcsv_to_xlsx(path, output, data, endian);
+bool libbcsv::bcsv_to_xlsx(const std::string& path, const std::string& output, const std::vector<std::uint8_t>& data, std::size_t offset, std::uint8_t endian) noexcept {
+    return libbcsv$cxxbridge1$bcsv_to_xlsx(path, output, data, offset, endian);
+}
+
+std::unique_ptr<std::vector<std::uint8_t>> libbcsv::bcsv_last_error() noexcept {
+    return std::unique_ptr<std::vector<std::uint8_t>>(libbcsv$cxxbridge1$bcsv_last_error());
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+//! Builds a `BCSV` from scratch, laying out the offsets and header once at the end.
+use crate::*;
+use types::{BCSV, Field, FieldType, Value};
+
+#[derive(Clone, Debug, Default)]
+pub struct BcsvBuilder {
+    fields: Vec<(String, FieldType)>,
+    rows: Vec<Vec<Value>>
+}
+
+impl BcsvBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field with the type's full mask and no shift. Names starting with `0x` are
+    /// treated as raw hashes.
+    pub fn add_field<S: Into<String>>(mut self, name: S, datatype: FieldType) -> Self {
+        self.fields.push((name.into(), datatype));
+        self
+    }
+
+    /// Adds a row with one value per field, in the order the fields were added.
+    pub fn push_row(mut self, values: Vec<Value>) -> Self {
+        self.rows.push(values);
+        self
+    }
+
+    /// Checks every row against the fields, then assigns the offsets, header sizes and
+    /// string offsets.
+    pub fn build(self) -> Result<BCSV, BCSVError> {
+        let mut result = BCSV::new();
+        result.header.entrycount = self.rows.len() as u32;
+        for (name, datatype) in &self.fields {
+            let hash = types::name_hash(name)?;
+            if result.fields.iter().any(|x| x.hash == hash) {
+                return Err(format!("Field {} has a duplicate hash 0x{:X}", name, hash).into());
+            }
+            result.fields.push(Field::from_hash(hash, *datatype));
+            result.columns.push(Vec::with_capacity(self.rows.len()));
+        }
+        for (i, row) in self.rows.into_iter().enumerate() {
+            if row.len() != self.fields.len() {
+                return Err(format!("Row {} has {} values, expected {}", i, row.len(), self.fields.len()).into());
+            }
+            for (((name, datatype), value), column) in self.fields.iter().zip(row).zip(&mut result.columns) {
+                if value.get_field_type() != *datatype {
+                    return Err(format!("Row {} has type {:?} for field {}, expected {:?}",
+                        i, value.get_field_type(), name, datatype).into());
+                }
+                column.push(value);
+            }
+        }
+        result.recompute_layout()?;
+        Ok(result)
+    }
+}
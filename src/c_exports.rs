@@ -1,5 +1,6 @@
-use std::{ffi::*, io::Cursor};
+use std::ffi::*;
 use crate::*;
+use crate::ffi::{self, to_endian};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -8,58 +9,243 @@ pub struct PtrInfo {
     pub len: usize
 }
 
+/// A null pointer was passed.
+pub const BCSV_ERR_NULL: i32 = -1;
+/// The data couldn't be parsed as a BCSV.
+pub const BCSV_ERR_READ: i32 = -2;
+/// A field index past the end of the table.
+pub const BCSV_ERR_RANGE: i32 = -3;
+/// A conversion failed; `bcsv_last_error` says why.
+pub const BCSV_ERR_CONVERT: i32 = -4;
+
+impl PtrInfo {
+    fn from_result(result: Result<Vec<u8>, BCSVError>) -> Self {
+        match result {
+            Ok(data) => {
+                let len = data.len();
+                PtrInfo { ptr: Box::into_raw(data.into_boxed_slice()).cast(), len }
+            },
+            Err(err) => {
+                ffi::set_last_error(err);
+                PtrInfo { ptr: std::ptr::null_mut(), len: 0 }
+            }
+        }
+    }
+}
+
+/// The message of the last failed call on this thread, or null if none has failed. The
+/// string stays valid until the next failure on the same thread.
+#[no_mangle]
+pub extern "C" fn bcsv_last_error() -> *const c_char {
+    ffi::with_last_error(|err| err.map_or(std::ptr::null(), |x| x.as_ptr()))
+}
+
+/// Frees the bytes of a `PtrInfo` returned by this library. A null `ptr` is ignored.
+///
+/// # Safety
+/// `info` must come from this library and not be freed twice.
 #[no_mangle]
 pub unsafe extern "C" fn free_PtrInfo(info: PtrInfo) {
     let PtrInfo { ptr, len } = info;
+    if ptr.is_null() {
+        return;
+    }
     let slice = std::slice::from_raw_parts_mut(ptr, len);
     drop(Box::<[u8]>::from_raw(slice));
 }
 
+/// `hash_path` as an owned string, or `None` if it's null.
+unsafe fn optional_path(hash_path: *const i8) -> Option<String> {
+    match hash_path.is_null() {
+        true => None,
+        false => Some(CStr::from_ptr(hash_path).to_string_lossy().into_owned())
+    }
+}
+
+/// CSV text for the BCSV at `offset` in `data`, or a null `PtrInfo` with the reason in
+/// `bcsv_last_error`. Fields are named from `hash_path`; a null `hash_path` shows them by
+/// hash, while a hash list that can't be read is an error.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `hash_path`, if not null, to a C string.
 #[no_mangle]
-pub unsafe extern "C" fn bcsv_to_csv(hash_path: *const i8, data: *const u8, len: usize, endian: u8) -> PtrInfo {
-    let buffer = std::slice::from_raw_parts(data, len).to_vec();
-    let endian = match endian {
-        0 => Endian::Big,
-        1 => Endian::Little,
-        _ => Endian::NATIVE
-    };
-    let mut reader = Cursor::new(buffer);
-    let mut bcsv = types::BCSV::new();
-    bcsv.read(&mut reader, endian).unwrap_or_default();
-    let hash_path = CStr::from_ptr(hash_path).to_string_lossy().into_owned();
-    let hashes = hash::read_hashes(hash_path).unwrap_or_default();
-    let text = bcsv.convert_to_csv(&hashes, false, ',');
-    let bx = Box::<[u8]>::from(text.as_bytes());
-    let len = bx.len();
-    PtrInfo {ptr: Box::into_raw(bx).cast(), len}
+pub unsafe extern "C" fn bcsv_to_csv(hash_path: *const i8, data: *const u8, len: usize, offset: usize, endian: u8) -> PtrInfo {
+    if data.is_null() {
+        return PtrInfo::from_result(Err("data is null".into()));
+    }
+    let buffer = std::slice::from_raw_parts(data, len);
+    let hash_path = optional_path(hash_path);
+    PtrInfo::from_result(ffi::bcsv_to_csv(hash_path.as_deref(), buffer, offset, endian).map(String::into_bytes))
 }
 
+/// Writes the BCSV at `offset` in `data` to `output_path` as xlsx, naming fields as
+/// `bcsv_to_csv` does. Returns 0 on success or a negative `BCSV_ERR_*` code, with the
+/// reason in `bcsv_last_error`.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes, `output_path` to a C string and `hash_path`,
+/// if not null, to a C string.
 #[no_mangle]
-pub unsafe extern "C" fn bcsv_to_xlsx(hash_path: *const i8, output_path: *const i8, data: *const u8, len: usize, endian: u8) {
-    let buffer = std::slice::from_raw_parts(data, len).to_vec();
-    let endian = match endian {
-        0 => Endian::Big,
-        1 => Endian::Little,
-        _ => Endian::NATIVE
-    };
-    let mut reader = Cursor::new(buffer);
-    let mut bcsv = types::BCSV::new();
-    bcsv.read(&mut reader, endian).unwrap_or_default();
-    let hash_path = CStr::from_ptr(hash_path).to_string_lossy().into_owned();
-    let hashes = hash::read_hashes(hash_path).unwrap_or_default();
-    bcsv.convert_to_xlsx(CStr::from_ptr(output_path).to_string_lossy(), &hashes, false).unwrap_or_default();
+pub unsafe extern "C" fn bcsv_to_xlsx(hash_path: *const i8, output_path: *const i8, data: *const u8, len: usize, offset: usize, endian: u8) -> i32 {
+    if data.is_null() || output_path.is_null() {
+        ffi::set_last_error("data or output_path is null");
+        return BCSV_ERR_NULL;
+    }
+    let buffer = std::slice::from_raw_parts(data, len);
+    let hash_path = optional_path(hash_path);
+    let output_path = CStr::from_ptr(output_path).to_string_lossy().into_owned();
+    match ffi::bcsv_to_xlsx(hash_path.as_deref(), &output_path, buffer, offset, endian) {
+        Ok(()) => 0,
+        Err(err) => {
+            ffi::set_last_error(err);
+            BCSV_ERR_CONVERT
+        }
+    }
 }
 
+/// The BCSV file for the CSV at `path`, or a null `PtrInfo` with the reason in
+/// `bcsv_last_error`.
+///
+/// # Safety
+/// `path` must be null or a C string.
 #[no_mangle]
 pub unsafe extern "C" fn csv_to_bcsv(path: *const i8, endian: u8) -> PtrInfo {
-    let endian = match endian {
-        0 => Endian::Big,
-        1 => Endian::Little,
-        _ => Endian::NATIVE
-    };
+    if path.is_null() {
+        return PtrInfo::from_result(Err("path is null".into()));
+    }
     let path = CStr::from_ptr(path).to_string_lossy().to_string();
-    let csv = csv_parse::CSV::from_path(path, ',').unwrap_or_default();
-    let data = csv.create_bcsv().to_bytes(endian).unwrap_or_default();
-    let len = data.len();
-    PtrInfo { ptr: Box::into_raw(data.into_boxed_slice()).cast(), len }
+    PtrInfo::from_result(ffi::csv_to_bcsv(&path, endian))
+}
+
+/// Parses just the header of `data` into `out_header`. Returns 0 on success or a negative
+/// `BCSV_ERR_*` code.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out_header` to a writable `Header`.
+#[no_mangle]
+pub unsafe extern "C" fn bcsv_read_header(data: *const u8, len: usize, endian: u8, out_header: *mut types::Header) -> i32 {
+    if data.is_null() || out_header.is_null() {
+        ffi::set_last_error("data or out_header is null");
+        return BCSV_ERR_NULL;
+    }
+    let buffer = std::slice::from_raw_parts(data, len);
+    match std::io::Cursor::new(buffer).read_type(to_endian(endian)) {
+        Ok(header) => {
+            *out_header = header;
+            0
+        },
+        Err(err) => {
+            ffi::set_last_error(err);
+            BCSV_ERR_READ
+        }
+    }
+}
+
+/// The header's field count, or a negative `BCSV_ERR_*` code.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bcsv_field_count(data: *const u8, len: usize, endian: u8) -> i64 {
+    let mut header = types::Header::default();
+    match bcsv_read_header(data, len, endian, &mut header) {
+        0 => header.fieldcount as i64,
+        err => err as i64
+    }
+}
+
+/// A parsed BCSV and the names to show for its fields, owned by C callers between
+/// `bcsv_open` and `bcsv_close`.
+pub struct BcsvHandle {
+    bcsv: types::BCSV,
+    hashes: std::collections::HashMap<u32, String>
+}
+
+/// Parses `data` and loads the names from `hash_path`, which may be null. Returns null,
+/// with the reason in `bcsv_last_error`, if the data isn't a valid BCSV or the hash list
+/// can't be read.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `hash_path`, if not null, to a C string.
+#[no_mangle]
+pub unsafe extern "C" fn bcsv_open(data: *const u8, len: usize, endian: u8, hash_path: *const i8) -> *mut BcsvHandle {
+    if data.is_null() {
+        ffi::set_last_error("data is null");
+        return std::ptr::null_mut();
+    }
+    let buffer = std::slice::from_raw_parts(data, len);
+    let bcsv = match types::BCSV::from_bytes(buffer, to_endian(endian)) {
+        Ok(bcsv) => bcsv,
+        Err(err) => {
+            ffi::set_last_error(err);
+            return std::ptr::null_mut();
+        }
+    };
+    let hashes = match hash_path.is_null() {
+        true => Default::default(),
+        false => match hash::read_hashes(CStr::from_ptr(hash_path).to_string_lossy().into_owned()) {
+            Ok(hashes) => hashes,
+            Err(err) => {
+                ffi::set_last_error(err);
+                return std::ptr::null_mut();
+            }
+        }
+    };
+    Box::into_raw(Box::new(BcsvHandle { bcsv, hashes }))
+}
+
+/// Frees a handle from `bcsv_open`. Null is ignored.
+///
+/// # Safety
+/// `handle` must come from `bcsv_open` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bcsv_close(handle: *mut BcsvHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// The number of fields in the handle's table, or `BCSV_ERR_NULL`.
+///
+/// # Safety
+/// `handle` must be null or come from `bcsv_open`.
+#[no_mangle]
+pub unsafe extern "C" fn bcsv_get_field_count(handle: *const BcsvHandle) -> i64 {
+    match handle.as_ref() {
+        Some(handle) => handle.bcsv.fields.len() as i64,
+        None => BCSV_ERR_NULL as i64
+    }
+}
+
+/// Writes the hash and datatype of field `index`, and its name (or `0x` hash) as a
+/// null-terminated string cut to fit `buf_len` bytes. Any out pointer may be null. Returns
+/// the untruncated name length in bytes, or a negative `BCSV_ERR_*` code.
+///
+/// # Safety
+/// `handle` must come from `bcsv_open`, and each non-null out pointer must be writable,
+/// `out_name_buf` for `buf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bcsv_get_field(handle: *const BcsvHandle, index: usize, out_hash: *mut u32,
+    out_type: *mut u8, out_name_buf: *mut c_char, buf_len: usize) -> i64 {
+    let handle = match handle.as_ref() {
+        Some(handle) => handle,
+        None => return BCSV_ERR_NULL as i64
+    };
+    let field = match handle.bcsv.fields.get(index) {
+        Some(field) => field,
+        None => return BCSV_ERR_RANGE as i64
+    };
+    if let Some(out) = out_hash.as_mut() {
+        *out = field.hash;
+    }
+    if let Some(out) = out_type.as_mut() {
+        *out = field.datatype;
+    }
+    let name = field.get_name(&handle.hashes);
+    if !out_name_buf.is_null() && buf_len > 0 {
+        let copied = name.len().min(buf_len - 1);
+        std::ptr::copy_nonoverlapping(name.as_ptr(), out_name_buf.cast(), copied);
+        *out_name_buf.add(copied) = 0;
+    }
+    name.len() as i64
 }
\ No newline at end of file
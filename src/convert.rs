@@ -0,0 +1,23 @@
+//! Free-function entry points for the common conversions. Each one delegates to the
+//! matching `BCSV`/`CSV` method.
+use std::collections::HashMap;
+use crate::*;
+
+/// CSV text for `bcsv`, `,`-delimited with SHORT and CHAR read as signed.
+pub fn convert_to_csv(bcsv: &types::BCSV, hashes: &HashMap<u32, String>) -> String {
+    bcsv.convert_to_csv(hashes, true, ',')
+}
+
+/// Lays out a parsed CSV as a BCSV file in `endian`. Every integer field gets `mask`,
+/// limited to the bits of its type, so `u32::MAX` keeps the full masks.
+pub fn convert_to_bcsv(csv: csv_parse::CSV, endian: Endian, mask: u32) -> BinResult<Vec<u8>> {
+    let mut bcsv = csv.create_bcsv();
+    for field in &mut bcsv.fields {
+        let datatype = field.get_field_type();
+        if matches!(datatype, types::FieldType::LONG | types::FieldType::ULONG
+            | types::FieldType::SHORT | types::FieldType::CHAR) {
+            field.mask = mask & datatype.mask();
+        }
+    }
+    bcsv.to_bytes(endian)
+}
@@ -1,5 +1,5 @@
 use crate::*;
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::Read, path::Path};
 
 #[derive(Debug, Default, Clone)]
 pub struct CSV {
@@ -24,21 +24,15 @@ impl CSV {
         result
     }
 
-    pub fn from_path<P: AsRef<Path>>(path: P, delim: char) -> Result<Self, BcsvError> {
+    pub fn from_path<P: AsRef<Path>>(path: P, delim: char) -> Result<Self, BCSVError> {
+        Self::from_reader(std::fs::File::open(path)?, delim)
+    }
+
+    pub fn from_reader<R: Read>(reader: R, delim: char) -> Result<Self, BCSVError> {
         let mut result = Self::default();
-        let mut rdr = csv::ReaderBuilder::new().delimiter(delim as u8).from_path(path)?;
+        let mut rdr = csv::ReaderBuilder::new().delimiter(delim as u8).from_reader(reader);
         for header in rdr.headers()?.iter() {
-            let split = header.split(':').collect::<Vec<_>>();
-            let name = split[0];
-            let dt = split[1];
-            let mut field = types::Field::default();
-            field.datatype = dt.parse()?;
-            field.mask = field.get_field_type().mask();
-            if !name.starts_with("0x") {
-                field.hash = hash::calchash(name);
-            } else {
-                field.hash = u32::from_str_radix(&name[2..], 16)?;
-            }
+            let field = types::Field::from_descriptor(header)?;
             result.fields.push(field);
             result.dict.insert(field, vec![]);
         }
@@ -54,7 +48,7 @@ impl CSV {
                         *l = entry.parse()?;
                     },
                     types::Value::STRING(st) => {
-                        *st = entry.as_bytes().try_into()?;
+                        *st = types::fixed_string(entry)?;
                     },
                     types::Value::FLOAT(f) => {
                         *f = entry.parse()?;
@@ -79,32 +73,21 @@ impl CSV {
                 }
             }
         }
-        let mut doff = 0;
-        let sorted = result.get_sorted_fields();
-        for f in sorted {
-            if let Some(og) = result.fields.iter_mut().find(|x| x.hash == f.hash) {
-                if let Some(values) = result.dict.remove(og) {
-                    if result.header.entrycount == 0 {
-                        result.header.entrycount = values.len() as u32;
-                    }
-                    og.dataoff = doff;
-                    doff += og.get_field_type().size();
-                    result.dict.insert(*og, values);
-                }
+        let mut bcsv = result.create_bcsv();
+        bcsv.header.entrycount = bcsv.columns.first().map_or(0, |x| x.len()) as u32;
+        bcsv.recompute_layout()?;
+        Ok(Self::from_laid_out(bcsv))
+    }
 
-            }
-        }
-        result.header.entrysize = result.fields.iter().map(|x| x.get_field_type().size() as u32).sum();
-        result.header.entrydataoff = 16 + (12 * result.header.fieldcount);
-        let mut table = string_table::StringTable::new();
-        table.update_offs(&mut result.entries);
-        for (_, vals) in &mut result.dict {
-            table.update_offs(vals);
-        }
-        Ok(result)
+    fn from_laid_out(bcsv: types::BCSV) -> Self {
+        let entries = bcsv.rows().flat_map(|row| row.into_iter().cloned()).collect();
+        let dict = bcsv.fields.iter().copied().zip(bcsv.columns).collect();
+        Self { header: bcsv.header, fields: bcsv.fields, entries, dict }
     }
 
     pub fn create_bcsv(self) -> types::BCSV {
-        types::BCSV {header: self.header, fields: self.fields, values: self.entries, dictonary: self.dict}
+        let mut dict = self.dict;
+        let columns = self.fields.iter().map(|x| dict.remove(x).unwrap_or_default()).collect();
+        types::BCSV {header: self.header, fields: self.fields, columns, ..Default::default()}
     }
 }
\ No newline at end of file
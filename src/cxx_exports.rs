@@ -1,4 +1,3 @@
-use std::io::Cursor;
 use cxx::*;
 
 use crate::*;
@@ -6,27 +5,14 @@ use crate::*;
 #[cxx::bridge(namespace = "libbcsv")]
 mod ffi {
     extern "Rust" {
-        fn bcsv_to_csv(path: &CxxString, data: &CxxVector<u8>, endian: u8) -> UniquePtr<CxxVector<u8>>;
-        fn bcsv_to_xlsx(path: &CxxString, output: &CxxString, data: &CxxVector<u8>, endian: u8);
+        fn bcsv_to_csv(path: &CxxString, data: &CxxVector<u8>, offset: usize, endian: u8) -> UniquePtr<CxxVector<u8>>;
+        fn bcsv_to_xlsx(path: &CxxString, output: &CxxString, data: &CxxVector<u8>, offset: usize, endian: u8) -> bool;
         fn csv_to_bcsv(path: &CxxString, endian: u8) -> UniquePtr<CxxVector<u8>>;
+        fn bcsv_last_error() -> UniquePtr<CxxVector<u8>>;
     }
 }
 
-
-pub fn bcsv_to_csv(path: &CxxString, data: &CxxVector<u8>, endian: u8) -> UniquePtr<CxxVector<u8>> {
-    let path = path.to_string_lossy().to_string();
-    let data = data.as_slice().to_vec();
-    let endian = match endian {
-        0 => Endian::Big,
-        1 => Endian::Little,
-        _ => Endian::NATIVE
-    };
-    let mut reader = Cursor::new(data);
-    let hashes = hash::read_hashes(path).unwrap_or_default();
-    let mut bcsv = types::BCSV::new();
-    bcsv.read(&mut reader, endian).unwrap_or_default();
-    let text = bcsv.convert_to_csv(&hashes, false, ',');
-    let bytes = text.as_bytes();
+fn to_vector(bytes: &[u8]) -> UniquePtr<CxxVector<u8>> {
     let mut result = CxxVector::new();
     let mut pin = result.pin_mut();
     for byte in bytes {
@@ -35,35 +21,54 @@ pub fn bcsv_to_csv(path: &CxxString, data: &CxxVector<u8>, endian: u8) -> Unique
     result
 }
 
-pub fn bcsv_to_xlsx(path: &CxxString, output: &CxxString, data: &CxxVector<u8>, endian: u8) {
-    let hash_path = path.to_string_lossy().to_string();
-    let data = data.as_slice().to_vec();
-    let endian = match endian {
-        0 => Endian::Big,
-        1 => Endian::Little,
-        _ => Endian::NATIVE
-    };
-    let mut reader = Cursor::new(data);
-    let mut bcsv = types::BCSV::new();
-    bcsv.read(&mut reader, endian).unwrap_or_default();
-    let hashes = hash::read_hashes(hash_path).unwrap_or_default();
-    let output_path = output.to_string_lossy();
-    bcsv.convert_to_xlsx(output_path, &hashes, false).unwrap_or_default();
+/// `path`, or `None` for an empty path, meaning no hash list.
+fn optional_path(path: &CxxString) -> Option<String> {
+    match path.is_empty() {
+        true => None,
+        false => Some(path.to_string_lossy().to_string())
+    }
+}
+
+/// The CSV text, or an empty vector with the reason in `bcsv_last_error`. An empty `path`
+/// shows fields by hash; a hash list that can't be read is an error.
+pub fn bcsv_to_csv(path: &CxxString, data: &CxxVector<u8>, offset: usize, endian: u8) -> UniquePtr<CxxVector<u8>> {
+    let path = optional_path(path);
+    match crate::ffi::bcsv_to_csv(path.as_deref(), data.as_slice(), offset, endian) {
+        Ok(text) => to_vector(text.as_bytes()),
+        Err(err) => {
+            crate::ffi::set_last_error(err);
+            to_vector(&[])
+        }
+    }
+}
+
+/// Whether the xlsx was written; on failure the reason is in `bcsv_last_error`. `path` is
+/// treated as in `bcsv_to_csv`.
+pub fn bcsv_to_xlsx(path: &CxxString, output: &CxxString, data: &CxxVector<u8>, offset: usize, endian: u8) -> bool {
+    let hash_path = optional_path(path);
+    let output_path = output.to_string_lossy().to_string();
+    match crate::ffi::bcsv_to_xlsx(hash_path.as_deref(), &output_path, data.as_slice(), offset, endian) {
+        Ok(()) => true,
+        Err(err) => {
+            crate::ffi::set_last_error(err);
+            false
+        }
+    }
 }
 
+/// The BCSV file, or an empty vector with the reason in `bcsv_last_error`.
 pub fn csv_to_bcsv(path: &CxxString, endian: u8) -> UniquePtr<CxxVector<u8>> {
-    let endian = match endian {
-        0 => Endian::Big,
-        1 => Endian::Little,
-        _ => Endian::NATIVE
-    };
     let path = path.to_string_lossy().to_string();
-    let csv = csv_parse::CSV::from_path(path, ',').unwrap_or_default();
-    let data = csv.create_bcsv().to_bytes(endian).unwrap_or_default();
-    let mut result = CxxVector::new();
-    let mut pin = result.pin_mut();
-    for byte in data {
-        pin.as_mut().push(byte);
+    match crate::ffi::csv_to_bcsv(&path, endian) {
+        Ok(data) => to_vector(&data),
+        Err(err) => {
+            crate::ffi::set_last_error(err);
+            to_vector(&[])
+        }
     }
-    result
+}
+
+/// The UTF-8 message of the last failed call on this thread, empty if none has failed.
+pub fn bcsv_last_error() -> UniquePtr<CxxVector<u8>> {
+    crate::ffi::with_last_error(|err| to_vector(err.map_or(&[][..], |x| x.as_bytes())))
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+use std::{fmt, io, num::{ParseFloatError, ParseIntError}};
+
+/// Every error the library's fallible APIs return.
+#[derive(Debug)]
+pub enum BCSVError {
+    Io(io::Error),
+    BinRW(binrw::Error),
+    Csv(csv::Error),
+    Xlsx(xlsxwriter::XlsxError),
+    ParseInt(ParseIntError),
+    ParseFloat(ParseFloatError),
+    Fmt(fmt::Error),
+    Hash(crate::hash::HashError),
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    /// An inconsistency in the table itself, described by the message.
+    Other(String)
+}
+
+impl fmt::Display for BCSVError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::BinRW(e) => e.fmt(f),
+            Self::Csv(e) => e.fmt(f),
+            Self::Xlsx(e) => e.fmt(f),
+            Self::ParseInt(e) => e.fmt(f),
+            Self::ParseFloat(e) => e.fmt(f),
+            Self::Fmt(e) => e.fmt(f),
+            Self::Hash(e) => e.fmt(f),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => e.fmt(f),
+            Self::Other(msg) => f.write_str(msg)
+        }
+    }
+}
+
+impl std::error::Error for BCSVError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::BinRW(e) => Some(e),
+            Self::Csv(e) => Some(e),
+            Self::Xlsx(e) => Some(e),
+            Self::ParseInt(e) => Some(e),
+            Self::ParseFloat(e) => Some(e),
+            Self::Fmt(e) => Some(e),
+            Self::Hash(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => Some(e),
+            Self::Other(_) => None
+        }
+    }
+}
+
+macro_rules! from_error {
+    ($($variant:ident($ty:ty)),* $(,)?) => {
+        $(impl From<$ty> for BCSVError {
+            fn from(e: $ty) -> Self {
+                Self::$variant(e)
+            }
+        })*
+    };
+}
+
+from_error!(Io(io::Error), BinRW(binrw::Error), Csv(csv::Error), Xlsx(xlsxwriter::XlsxError),
+    ParseInt(ParseIntError), ParseFloat(ParseFloatError), Fmt(fmt::Error), Hash(crate::hash::HashError),
+    Other(String));
+
+#[cfg(feature = "serde")]
+from_error!(Json(serde_json::Error));
+
+impl From<&str> for BCSVError {
+    fn from(msg: &str) -> Self {
+        Self::Other(msg.to_string())
+    }
+}
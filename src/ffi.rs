@@ -0,0 +1,61 @@
+//! Shared pieces of the `c_exports` and `cxx` layers: the conversions they expose, with
+//! real errors, and the per-thread record of the last one.
+use std::{cell::RefCell, ffi::CString};
+use crate::*;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records `err` as the calling thread's last error.
+pub(crate) fn set_last_error<E: std::fmt::Display>(err: E) {
+    let msg = CString::new(err.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|x| *x.borrow_mut() = Some(msg));
+}
+
+/// Calls `f` with the calling thread's last error, if any call on it has failed yet.
+pub(crate) fn with_last_error<R, F: FnOnce(Option<&CString>) -> R>(f: F) -> R {
+    LAST_ERROR.with(|x| f(x.borrow().as_ref()))
+}
+
+/// 0 is big endian, 1 little endian, anything else the native endian.
+pub(crate) fn to_endian(endian: u8) -> Endian {
+    match endian {
+        0 => Endian::Big,
+        1 => Endian::Little,
+        _ => Endian::NATIVE
+    }
+}
+
+fn read_at(data: &[u8], offset: usize, endian: u8) -> Result<types::BCSV, BCSVError> {
+    match data.get(offset..) {
+        Some(data) => Ok(types::BCSV::from_bytes(data, to_endian(endian))?),
+        None => Err(format!("Offset {} is past the end of the {} bytes of data", offset, data.len()).into())
+    }
+}
+
+/// The names in `hash_path`, or none when there's no path. A path that can't be read is
+/// an error, not an empty list.
+fn read_names(hash_path: Option<&str>) -> Result<std::collections::HashMap<u32, String>, BCSVError> {
+    match hash_path {
+        Some(path) => Ok(hash::read_hashes(path)?),
+        None => Ok(Default::default())
+    }
+}
+
+pub(crate) fn bcsv_to_csv(hash_path: Option<&str>, data: &[u8], offset: usize, endian: u8) -> Result<String, BCSVError> {
+    let bcsv = read_at(data, offset, endian)?;
+    let hashes = read_names(hash_path)?;
+    Ok(bcsv.convert_to_csv(&hashes, false, ','))
+}
+
+pub(crate) fn bcsv_to_xlsx(hash_path: Option<&str>, output_path: &str, data: &[u8], offset: usize, endian: u8) -> Result<(), BCSVError> {
+    let bcsv = read_at(data, offset, endian)?;
+    let hashes = read_names(hash_path)?;
+    bcsv.convert_to_xlsx(output_path, &hashes, false)
+}
+
+pub(crate) fn csv_to_bcsv(path: &str, endian: u8) -> Result<Vec<u8>, BCSVError> {
+    let csv = csv_parse::CSV::from_path(path, ',')?;
+    Ok(convert::convert_to_bcsv(csv, to_endian(endian), u32::MAX)?)
+}
@@ -1,22 +1,242 @@
-use std::{path::Path, collections::HashMap, num::Wrapping};
+use std::{path::Path, collections::{HashMap, HashSet}, fmt, num::Wrapping};
+#[cfg(feature = "serde")]
+use crate::types::FieldType;
+
+/// Why loading a hash list failed: the file couldn't be read, or its contents couldn't be
+/// parsed.
+#[derive(Debug)]
+pub enum HashError {
+    Io(std::io::Error),
+    Parse(String)
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Couldn't read hash list: {}", err),
+            Self::Parse(msg) => write!(f, "Couldn't parse hash list: {}", msg)
+        }
+    }
+}
+
+impl std::error::Error for HashError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(_) => None
+        }
+    }
+}
+
+impl From<std::io::Error> for HashError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for HashError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Parse(err.to_string())
+    }
+}
 
 pub fn calchash(text: &str) -> u32 {
+    calchash_bytes(text.as_bytes())
+}
+
+/// `calchash` over raw bytes, for names that aren't valid UTF-8 such as SHIFT_JIS.
+pub fn calchash_bytes(bytes: &[u8]) -> u32 {
     let mut output = Wrapping(0u32);
-    for char in text.bytes() {
-        output = Wrapping(char as u32) + (output * Wrapping(0x1f));
+    for char in bytes {
+        output = Wrapping(*char as u32) + (output * Wrapping(0x1f));
     }
     output.0
 }
 
-pub fn read_hashes<P: AsRef<Path>>(path: P) -> std::io::Result<HashMap<u32, String>> {
+/// The older JMap field hash, `(hash << 8) + byte` reduced modulo `0x1FFFFD9`.
+pub fn calc_old_hash(text: &str) -> u32 {
+    calc_old_hash_bytes(text.as_bytes())
+}
+
+/// `calc_old_hash` over raw bytes.
+pub fn calc_old_hash_bytes(bytes: &[u8]) -> u32 {
+    let mut output = 0u64;
+    for char in bytes {
+        output = ((output << 8) + *char as u64) % 0x1FFFFD9;
+    }
+    output as u32
+}
+
+/// Keys each name by `calc_old_hash` when `old` is set, otherwise by `calchash`.
+pub fn rehash_list(names: &[String], old: bool) -> HashMap<u32, String> {
+    let hasher = match old {
+        true => calc_old_hash,
+        false => calchash
+    };
+    names.iter().map(|name| (hasher(name), name.clone())).collect()
+}
+
+/// Searches for a name of at most `max_len` bytes from `charset` whose `calchash` is
+/// `target`, shortest first. The last byte of each candidate is solved for directly, so
+/// only `charset.len().pow(len - 1)` prefixes are tried per length. `charset` is expected
+/// to be ASCII.
+pub fn brute_force(target: u32, charset: &[u8], max_len: usize) -> Option<String> {
+    let mut allowed = [false; 256];
+    for &b in charset {
+        allowed[b as usize] = true;
+    }
+    if target == 0 {
+        return Some(String::new());
+    }
+    (1..=max_len).find_map(|len| {
+        let mut buf = vec![0u8; len];
+        match solve_last(target, charset, &allowed, &mut buf, 0, Wrapping(0)) {
+            true => String::from_utf8(buf).ok(),
+            false => None
+        }
+    })
+}
+
+fn solve_last(target: u32, charset: &[u8], allowed: &[bool; 256], buf: &mut [u8], depth: usize,
+    hash: Wrapping<u32>) -> bool {
+    if depth + 1 == buf.len() {
+        let need = (Wrapping(target) - hash * Wrapping(0x1f)).0;
+        if need < 256 && allowed[need as usize] {
+            buf[depth] = need as u8;
+            return true;
+        }
+        return false;
+    }
+    for &b in charset {
+        buf[depth] = b;
+        if solve_last(target, charset, allowed, buf, depth + 1, Wrapping(b as u32) + hash * Wrapping(0x1f)) {
+            return true;
+        }
+    }
+    false
+}
+
+/// `brute_force` for several hashes at once, sharing one enumeration of the candidates.
+/// Each length is split across threads by first byte. Returns the shortest name found for
+/// every target that has one.
+pub fn brute_force_many(targets: &[u32], charset: &[u8], max_len: usize) -> HashMap<u32, String> {
+    let mut remaining: HashSet<u32> = targets.iter().copied().collect();
+    let mut result = HashMap::new();
+    if remaining.remove(&0) {
+        result.insert(0, String::new());
+    }
+    let threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+    let chunk = charset.len().div_ceil(threads).max(1);
+    for len in 1..=max_len {
+        if remaining.is_empty() {
+            break;
+        }
+        let found: Vec<(u32, Vec<u8>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = charset.chunks(chunk).map(|firsts| {
+                let remaining = &remaining;
+                scope.spawn(move || {
+                    let mut found = vec![];
+                    let mut buf = vec![0u8; len];
+                    for &b in firsts {
+                        buf[0] = b;
+                        enumerate(remaining, charset, &mut buf, 1, Wrapping(b as u32), &mut found);
+                    }
+                    found
+                })
+            }).collect();
+            handles.into_iter().flat_map(|x| x.join().unwrap_or_default()).collect()
+        });
+        for (hash, name) in found {
+            if remaining.remove(&hash) {
+                if let Ok(name) = String::from_utf8(name) {
+                    result.insert(hash, name);
+                }
+            }
+        }
+    }
+    result
+}
+
+fn enumerate(targets: &HashSet<u32>, charset: &[u8], buf: &mut [u8], depth: usize,
+    hash: Wrapping<u32>, found: &mut Vec<(u32, Vec<u8>)>) {
+    if depth == buf.len() {
+        if targets.contains(&hash.0) {
+            found.push((hash.0, buf.to_vec()));
+        }
+        return;
+    }
+    for &b in charset {
+        buf[depth] = b;
+        enumerate(targets, charset, buf, depth + 1, Wrapping(b as u32) + hash * Wrapping(0x1f), found);
+    }
+}
+
+/// Cuts a trailing comment, a `#` preceded by whitespace, off the end of a line.
+fn strip_comment(line: &str) -> &str {
+    let comment = line.match_indices('#')
+        .find(|(i, _)| *i > 0 && line.as_bytes()[i - 1].is_ascii_whitespace());
+    match comment {
+        Some((i, _)) => line[..i].trim_end(),
+        None => line
+    }
+}
+
+pub fn read_hashes<P: AsRef<Path>>(path: P) -> Result<HashMap<u32, String>, HashError> {
     let text = std::fs::read_to_string(path)?;
     let mut result = HashMap::new();
     for line in text.split('\n') {
         if line.starts_with('#') {
             continue;
         }
+        let line = strip_comment(line);
         let hash = calchash(line);
         result.insert(hash, String::from(line));
     }
     Ok(result)
+}
+
+/// Reads every file with `read_hashes` and merges them, later files winning on collision.
+pub fn read_hashes_many<P: AsRef<Path>>(paths: &[P]) -> Result<HashMap<u32, String>, HashError> {
+    let mut result = HashMap::new();
+    for path in paths {
+        merge_hashes(&mut result, read_hashes(path)?);
+    }
+    Ok(result)
+}
+
+/// Adds every name in `from` to `into`, replacing names already there for the same hash.
+pub fn merge_hashes(into: &mut HashMap<u32, String>, from: HashMap<u32, String>) {
+    into.extend(from);
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SchemaEntry {
+    name: String,
+    #[serde(rename = "type")]
+    datatype: Option<FieldType>
+}
+
+/// Reads a JSON schema of the form `{ "0xHASH": {"name": "...", "type": "LONG"} }`.
+/// Returns the names keyed by hash, plus the expected type of every entry that has one.
+/// Keys without a `0x` prefix are hashed as names.
+#[cfg(feature = "serde")]
+pub fn read_hashes_json<P: AsRef<Path>>(path: P) -> Result<(HashMap<u32, String>, HashMap<u32, FieldType>), HashError> {
+    let text = std::fs::read_to_string(path)?;
+    let schema: HashMap<String, SchemaEntry> = serde_json::from_str(&text)?;
+    let mut names = HashMap::new();
+    let mut types = HashMap::new();
+    for (key, entry) in schema {
+        let hash = match key.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16)
+                .map_err(|_| HashError::Parse(format!("\"{}\" is not a valid hash", key)))?,
+            None => calchash(&key)
+        };
+        if let Some(datatype) = entry.datatype {
+            types.insert(hash, datatype);
+        }
+        names.insert(hash, entry.name);
+    }
+    Ok((names, types))
 }
\ No newline at end of file
@@ -3,15 +3,21 @@ pub mod hash;
 pub mod types;
 pub mod csv_parse;
 pub mod string_table;
-pub type BcsvError = Box<dyn Error>;
+pub mod convert;
+pub mod builder;
+pub mod error;
+pub use error::BCSVError;
+/// Kept so code naming the old alias still compiles; it is now the `BCSVError` enum.
+pub type BcsvError = BCSVError;
 pub use binrw::Endian;
 pub use binrw;
 pub use csv;
 // Crate only exports
 use binrw::prelude::*;
-use std::error::Error;
 // Feature only mods
 
+#[cfg(any(feature = "c_exports", feature = "cxx"))]
+mod ffi;
 #[cfg(feature = "c_exports")]
 pub mod c_exports;
 #[cfg(feature = "cxx")]
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::{Read, Seek, SeekFrom}};
 use crate::*;
+use encoding_rs::{Encoding, SHIFT_JIS};
 
 #[derive(Clone, Debug, Default)]
 pub struct StringTable {
@@ -11,21 +12,114 @@ impl StringTable {
     pub fn new() -> Self {
         StringTable { table: HashMap::new(), off: 0 }
     }
+    /// A table with the empty string already at offset 0, so that `update_offs` points
+    /// every empty STRINGOFF there, as many game files expect.
+    pub fn new_with_empty() -> Self {
+        let mut result = Self::new();
+        result.push("");
+        result
+    }
+    /// Parses the null-terminated SHIFT_JIS strings of an existing string section, keeping
+    /// each string's offset. Bytes after the last terminator, such as padding, are ignored.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut result = Self::new();
+        let end = data.iter().rposition(|x| *x == 0).map_or(0, |x| x + 1);
+        let mut start = 0;
+        for bytes in data[..end].split_inclusive(|x| *x == 0) {
+            let (str, _, _) = SHIFT_JIS.decode(&bytes[..bytes.len() - 1]);
+            result.table.entry(str.into_owned()).or_insert(start as u32);
+            start += bytes.len();
+        }
+        result.off = end as u32;
+        result
+    }
     pub fn push<A: AsRef<str>>(&mut self, item: A) -> &mut Self {
         let str = String::from(item.as_ref());
-        let len = str.len() as u32 + 1;
+        let len = SHIFT_JIS.encode(&str).0.len() as u32 + 1;
         if !self.table.contains_key(&str) {
             self.table.insert(str, self.off);
             self.off += len;
         }
         self
     }
+    /// Builds a table like repeated `push`, except that a string which is the tail of a
+    /// string already in the table reuses the end of that string instead of being appended.
+    pub fn build_compact<I: IntoIterator<Item = A>, A: AsRef<str>>(items: I) -> Self {
+        let mut result = Self::new();
+        let mut stored: Vec<(Vec<u8>, u32)> = vec![];
+        for item in items {
+            let str = item.as_ref();
+            if result.table.contains_key(str) {
+                continue;
+            }
+            let data = SHIFT_JIS.encode(str).0.into_owned();
+            let shared = stored.iter().find(|(x, _)| x.ends_with(&data))
+                .map(|(x, off)| off + (x.len() - data.len()) as u32);
+            match shared {
+                Some(off) => {
+                    result.table.insert(String::from(str), off);
+                },
+                None => {
+                    stored.push((data, result.off));
+                    result.push(str);
+                }
+            }
+        }
+        result
+    }
     pub fn find(&self, key: &String) -> Option<&u32> {
         self.table.get(key)
     }
+    /// The strings and their offsets in offset order, which for a table built with `push`
+    /// is the order they were first pushed.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> + '_ {
+        self.sorted().into_iter().map(|(str, off)| (str.as_str(), *off))
+    }
+    fn sorted(&self) -> Vec<(&String, &u32)> {
+        let mut strings = self.table.iter().collect::<Vec<_>>();
+        strings.sort_by_key(|(_, off)| **off);
+        strings
+    }
+    /// Total size of the strings and their terminators.
+    pub fn byte_len(&self) -> u32 {
+        self.off
+    }
+    /// Encodes every string as SHIFT_JIS in offset order, each followed by a null, so the
+    /// result can be written as the string section in one go. Errors on a string that has
+    /// no SHIFT_JIS encoding.
+    pub fn build_bytes(&self) -> std::io::Result<Vec<u8>> {
+        match self.encode() {
+            (_, Some(str)) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("String \"{}\" can't be encoded as SHIFT_JIS", str))),
+            (result, None) => Ok(result)
+        }
+    }
+    /// The same bytes as `build_bytes`, except that characters with no SHIFT_JIS encoding
+    /// are replaced with numeric character references instead of failing.
     pub fn data(&self) -> Vec<u8> {
-        self.table.keys().map(|x| x.clone().into_bytes())
-        .fold(vec![], |mut v, mut x| {v.append(&mut x); v.push(0); v})
+        self.encode().0
+    }
+    /// Encodes the strings in offset order, along with the first one that had to be
+    /// encoded lossily.
+    fn encode(&self) -> (Vec<u8>, Option<&str>) {
+        let mut result = Vec::with_capacity(self.off as usize);
+        let mut lossy = None;
+        for (str, off) in self.sorted() {
+            let (data, _, had_errors) = SHIFT_JIS.encode(str);
+            if had_errors && lossy.is_none() {
+                lossy = Some(str.as_str());
+            }
+            if (*off as usize) < result.len() {
+                // The tail of a string already written, from `build_compact`.
+                continue;
+            }
+            if result.len() < *off as usize {
+                result.resize(*off as usize, 0);
+            }
+            result.extend_from_slice(&data);
+            result.push(0);
+        }
+        (result, lossy)
     }
     pub fn update_offs(&mut self, entries: &mut Vec<types::Value>) {
         for entry in entries {
@@ -37,4 +131,63 @@ impl StringTable {
             }
         }
     }
+}
+
+struct StringTableReader<'a, R> {
+    reader: &'a mut R,
+    start: u64,
+    pos: u64,
+    end: u64,
+    encoding: &'static Encoding
+}
+
+impl<'a, R: Read + Seek> Iterator for StringTableReader<'a, R> {
+    type Item = (u32, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.end {
+            return None;
+        }
+        let off = (self.pos - self.start) as u32;
+        if self.reader.seek(SeekFrom::Start(self.pos)).is_err() {
+            self.pos = self.end;
+            return None;
+        }
+        let mut bytes = vec![];
+        let mut byte = [0u8];
+        let mut terminated = false;
+        while self.pos < self.end {
+            if self.reader.read_exact(&mut byte).is_err() {
+                self.pos = self.end;
+                return None;
+            }
+            self.pos += 1;
+            if byte[0] == 0 {
+                terminated = true;
+                break;
+            }
+            bytes.push(byte[0]);
+        }
+        if !terminated {
+            // Like `Value::calc_stringoff`: an unterminated string runs into the 0x40
+            // padding, which is left out, and the padding alone isn't a string.
+            while bytes.last() == Some(&0x40) {
+                bytes.pop();
+            }
+            if bytes.is_empty() {
+                return None;
+            }
+        }
+        let (dec, _, _) = self.encoding.decode(&bytes);
+        Some((off, dec.into()))
+    }
+}
+
+/// Walks the null-terminated strings between `start` and `end`, yielding each string's
+/// offset relative to `start` along with its decoded text. A string cut off by `end`
+/// is yielded without its trailing 0x40 padding, so `end` may be the end of the file;
+/// iteration stops there or on the first read error.
+pub fn read_string_table<'a, R: Read + Seek>(reader: &'a mut R, start: u64, end: u64,
+    encoding: &'static Encoding) -> impl Iterator<Item = (u32, String)> + 'a {
+    StringTableReader { reader, start, pos: start, end, encoding }
 }
\ No newline at end of file
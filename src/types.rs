@@ -1,9 +1,11 @@
-use std::{collections::HashMap, io::{Cursor, Read, Seek, SeekFrom, Write}};
+use std::{collections::{HashMap, HashSet}, io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write}, path::Path};
 
 use crate::*;
 use encoding_rs::SHIFT_JIS;
 
-#[derive(Clone, Copy, Debug, Default, BinRead, BinWrite)]
+#[derive(Clone, Copy, Debug, Default, BinRead, BinWrite, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
 pub struct Header {
     pub entrycount: u32,
     pub fieldcount: u32,
@@ -13,11 +15,68 @@ pub struct Header {
 
 impl Header {
     pub const fn stringoffset(&self) -> u64 {
-        (self.entrydataoff + self.entrycount * self.entrysize) as u64
+        self.entrydataoff as u64 + self.entrycount as u64 * self.entrysize as u64
+    }
+
+    /// Whether the header could describe a file of `file_len` bytes: the field table ends
+    /// by `entrydataoff`, `entrysize` fits the `u16` data offsets, and the entries end by
+    /// the end of the file.
+    pub const fn is_consistent(&self, file_len: u64) -> bool {
+        16 + 12 * self.fieldcount as u64 <= self.entrydataoff as u64
+            && self.entrysize <= 0x10000
+            && self.stringoffset() <= file_len
+    }
+
+    pub const fn entry_count(&self) -> u32 {
+        self.entrycount
+    }
+
+    pub const fn field_count(&self) -> u32 {
+        self.fieldcount
+    }
+
+    pub const fn entry_data_offset(&self) -> u32 {
+        self.entrydataoff
+    }
+
+    pub const fn entry_size(&self) -> u32 {
+        self.entrysize
+    }
+
+    /// Sets `entrycount`. No other header field changes, but `stringoffset()` moves with it.
+    pub fn set_entry_count(&mut self, count: u32) {
+        self.entrycount = count;
+    }
+
+    /// Sets `fieldcount` and moves `entrydataoff` to just past the field table. Errors,
+    /// leaving the header unchanged, if `entrydataoff` would overflow.
+    pub fn set_field_count(&mut self, count: u32) -> Result<(), BCSVError> {
+        self.entrydataoff = count.checked_mul(12).and_then(|x| x.checked_add(16))
+            .ok_or_else(|| format!("Field count {} overflows entrydataoff", count))?;
+        self.fieldcount = count;
+        Ok(())
+    }
+
+    /// Sets `fieldcount` and `entrydataoff` as `set_field_count` does, and `entrysize` to the
+    /// summed size of `fields`, counting packed fields that share a `dataoff` once as
+    /// `BCSV::validate` does. Errors, leaving the header unchanged, if either overflows.
+    pub fn set_fields(&mut self, fields: &[Field]) -> Result<(), BCSVError> {
+        let mut offsets = HashSet::new();
+        let size: u64 = fields.iter().filter(|x| offsets.insert(x.dataoff))
+            .map(|x| x.data_size() as u64).sum();
+        let entrysize = u32::try_from(size)
+            .map_err(|_| format!("Fields take {} bytes, which overflows entrysize", size))?;
+        let count = u32::try_from(fields.len())
+            .map_err(|_| format!("Field count {} overflows fieldcount", fields.len()))?;
+        self.set_field_count(count)?;
+        self.entrysize = entrysize;
+        Ok(())
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String"))]
 #[repr(u8)]
 pub enum FieldType {
     LONG,
@@ -40,6 +99,50 @@ impl From<u8> for FieldType {
     }
 }
 
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Parses a type name such as `LONG`, or a numeric datatype from `0` to `7`.
+impl std::str::FromStr for FieldType {
+    type Err = BCSVError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "LONG" => Self::LONG,
+            "STRING" => Self::STRING,
+            "FLOAT" => Self::FLOAT,
+            "ULONG" => Self::ULONG,
+            "SHORT" => Self::SHORT,
+            "CHAR" => Self::CHAR,
+            "STRINGOFF" => Self::STRINGOFF,
+            "NULL" => Self::NULL,
+            _ => match s.parse::<u8>() {
+                Ok(datatype) if datatype <= Self::NULL as u8 => Self::from(datatype),
+                _ => return Err(format!("\"{}\" is not a field type", s).into())
+            }
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<FieldType> for String {
+    fn from(datatype: FieldType) -> Self {
+        datatype.to_string()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<String> for FieldType {
+    type Error = BCSVError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 impl FieldType {
     pub const fn size(&self) -> u16 {
         match self {
@@ -60,6 +163,25 @@ impl FieldType {
         }
     }
 
+    /// Inclusive range of integers the type can hold. `signed` only affects SHORT and CHAR,
+    /// which are stored unsigned but often read as signed; LONG is always signed and ULONG
+    /// always unsigned. Returns `None` for non-integer types.
+    pub const fn value_range(&self, signed: bool) -> Option<(i64, i64)> {
+        match self {
+            Self::LONG => Some((i32::MIN as i64, i32::MAX as i64)),
+            Self::ULONG => Some((0, u32::MAX as i64)),
+            Self::SHORT => match signed {
+                true => Some((i16::MIN as i64, i16::MAX as i64)),
+                false => Some((0, u16::MAX as i64))
+            },
+            Self::CHAR => match signed {
+                true => Some((i8::MIN as i64, i8::MAX as i64)),
+                false => Some((0, u8::MAX as i64))
+            },
+            Self::STRING | Self::FLOAT | Self::STRINGOFF | Self::NULL => None
+        }
+    }
+
     pub const fn order(&self) -> i32 {
         match self {
             Self::NULL => -1,
@@ -75,6 +197,8 @@ impl FieldType {
 }
 
 #[derive(Clone, Copy, Debug, Default, BinRead, BinWrite, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize),
+    serde(into = "FieldRepr", try_from = "FieldRepr"))]
 pub struct Field {
     pub hash: u32,
     pub mask: u32,
@@ -87,6 +211,18 @@ impl Field {
     pub fn get_field_type(&self) -> FieldType {
         self.datatype.into()
     }
+    /// Bytes the field takes up in each entry; the sum over each distinct `dataoff` is
+    /// `entrysize`.
+    pub fn data_size(&self) -> u16 {
+        self.get_field_type().size()
+    }
+    /// Whether the field can share its slot with other bitfields: an integer type whose
+    /// mask covers some, but not all, of the type's bits.
+    pub fn is_packable(&self) -> bool {
+        let datatype = self.get_field_type();
+        matches!(datatype, FieldType::LONG | FieldType::ULONG | FieldType::SHORT | FieldType::CHAR)
+            && self.mask != 0 && self.mask != datatype.mask()
+    }
     pub fn get_name(&self, hashes: &HashMap<u32, String>) -> String {
         if let Some(val) = hashes.get(&self.hash) {
             val.clone()
@@ -94,6 +230,177 @@ impl Field {
             format!("0x{:X}", self.hash)
         }
     }
+
+    /// A field for a column known only by its hash, with the type's full mask and no shift.
+    pub fn from_hash(hash: u32, datatype: FieldType) -> Self {
+        Self { hash, mask: datatype.mask(), datatype: datatype as u8, ..Default::default() }
+    }
+
+    /// Formats the `name:type` column header. `%`, `:` and `delim` are percent-encoded in
+    /// the name so the header always splits back into the same name.
+    pub fn to_descriptor(&self, hashes: &HashMap<u32, String>, delim: char) -> String {
+        let name = match hashes.get(&self.hash) {
+            Some(name) => encode_name(name, delim),
+            None => format!("0x{:X}", self.hash)
+        };
+        format!("{}:{}", name, self.datatype)
+    }
+
+    /// Parses a `name:type` or `name:mask:shift:type` column header. The type is either the
+    /// numeric datatype or a `FieldType` name; `mask` (decimal or `0x` hex) and `shift` are
+    /// optional and default to the type's mask and 0. The name is percent-decoded and
+    /// hashed, unless it is a raw `0x` hash.
+    pub fn from_descriptor(desc: &str) -> Result<Self, BCSVError> {
+        let parts = desc.split(':').collect::<Vec<_>>();
+        let (name, mask, shift, dt) = match parts[..] {
+            [name, dt] => (name, None, None, dt),
+            [name, mask, shift, dt] => (name, Some(mask), Some(shift), dt),
+            [_] => return Err(format!("Descriptor \"{}\" has no type", desc).into()),
+            _ => return Err(format!("Descriptor \"{}\" isn't name:type or name:mask:shift:type", desc).into())
+        };
+        let datatype: FieldType = dt.parse()?;
+        let hash = match name.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16)?,
+            None => hash::calchash(&decode_name(name))
+        };
+        let mask = match mask {
+            Some(mask) => match mask.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16)?,
+                None => mask.parse()?
+            },
+            None => datatype.mask()
+        };
+        let shift = match shift {
+            Some(shift) => shift.parse()?,
+            None => 0
+        };
+        Ok(Self { hash, mask, shift, datatype: datatype as u8, ..Default::default() })
+    }
+
+    /// Builds a packed integer field covering `bit_width` bits starting at `bit_offset`,
+    /// computing `mask` and `shift` from the bit range.
+    pub fn with_bitfield(name: &str, datatype: FieldType, bit_offset: u8, bit_width: u8) -> Result<Self, BCSVError> {
+        let type_bits = match datatype {
+            FieldType::LONG | FieldType::ULONG | FieldType::SHORT | FieldType::CHAR => datatype.size() as u32 * 8,
+            _ => return Err(format!("{:?} fields can't be bitfields", datatype).into())
+        };
+        if bit_width == 0 || bit_offset as u32 + bit_width as u32 > type_bits {
+            return Err(format!("Bits {}..{} don't fit a {}-bit {:?} field",
+                bit_offset, bit_offset as u32 + bit_width as u32, type_bits, datatype).into());
+        }
+        let mask = (((1u64 << bit_width) - 1) << bit_offset) as u32;
+        Ok(Self { hash: hash::calchash(name), mask, shift: bit_offset, datatype: datatype as u8, ..Default::default() })
+    }
+}
+
+/// Serialized form of a `Field`. `dataoff` is left out since it's derived from the field
+/// order; on deserialize, `hash` may be replaced by a `name` to hash and `mask` defaults to
+/// the type's mask.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FieldRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(rename = "type")]
+    datatype: FieldType,
+    #[serde(default)]
+    mask: Option<u32>,
+    #[serde(default)]
+    shift: u8
+}
+
+#[cfg(feature = "serde")]
+impl From<Field> for FieldRepr {
+    fn from(field: Field) -> Self {
+        Self { hash: Some(field.hash), name: None, datatype: field.get_field_type(),
+            mask: Some(field.mask), shift: field.shift }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<FieldRepr> for Field {
+    type Error = String;
+
+    fn try_from(repr: FieldRepr) -> Result<Self, Self::Error> {
+        let hash = match (repr.hash, &repr.name) {
+            (Some(hash), _) => hash,
+            (None, Some(name)) => hash::calchash(name),
+            (None, None) => return Err(String::from("Field needs a hash or a name"))
+        };
+        Ok(Self { hash, mask: repr.mask.unwrap_or(repr.datatype.mask()), dataoff: 0,
+            shift: repr.shift, datatype: repr.datatype as u8 })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Value {
+    /// The JSON form used by `BCSV::to_json`: numbers, with SHORT and CHAR as signed, and
+    /// text for both string types.
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::LONG(l) => (*l).into(),
+            Self::ULONG(ul) => (*ul).into(),
+            Self::SHORT(sh) => (*sh as i16).into(),
+            Self::CHAR(c) => (*c as i8).into(),
+            Self::FLOAT(f) => (*f as f64).into(),
+            Self::STRING(_) | Self::STRINGOFF(_) => self.get_string(true).into(),
+            Self::NULL => serde_json::Value::Null
+        }
+    }
+
+    fn from_json(field: Field, json: &serde_json::Value) -> Result<Self, BCSVError> {
+        let bad = || -> BCSVError {
+            format!("{} is not a valid {:?} for field 0x{:X}", json, field.get_field_type(), field.hash).into()
+        };
+        let int = json.as_i64();
+        let mut value = Value::new(field);
+        match &mut value {
+            Self::LONG(l) => *l = int.and_then(|x| i32::try_from(x).ok()).ok_or_else(bad)?,
+            Self::ULONG(ul) => *ul = int.and_then(|x| u32::try_from(x).ok()).ok_or_else(bad)?,
+            Self::SHORT(sh) => *sh = int.and_then(|x| i32::try_from(x).ok()).ok_or_else(bad)? as u16,
+            Self::CHAR(c) => *c = int.and_then(|x| i16::try_from(x).ok()).ok_or_else(bad)? as u8,
+            Self::FLOAT(f) => *f = json.as_f64().ok_or_else(bad)? as f32,
+            Self::STRING(s) => *s = fixed_string(json.as_str().ok_or_else(bad)?)?,
+            Self::STRINGOFF((_, st)) => *st = json.as_str().ok_or_else(bad)?.to_string(),
+            Self::NULL => {}
+        }
+        Ok(value)
+    }
+}
+
+fn encode_name(name: &str, delim: char) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c == '%' || c == ':' || c == delim {
+            let mut buf = [0u8; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                result += &format!("%{:02X}", byte);
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn decode_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = name.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                result.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        result.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&result).into_owned()
 }
 
 impl PartialOrd for Field {
@@ -108,6 +415,34 @@ impl Ord for Field {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// Shortest text that parses back to the same `f32`.
+    #[default]
+    Shortest,
+    /// Fixed number of decimal places, e.g. `Fixed(6)` gives `0.100000`. The value is
+    /// rounded to that many places, so the text doesn't always parse back to the same
+    /// `f32`; use `Shortest` for CSV that will be imported again.
+    Fixed(usize)
+}
+
+impl FloatFormat {
+    pub fn format(&self, value: f32) -> String {
+        match self {
+            Self::Shortest => format!("{}", value),
+            Self::Fixed(prec) => format!("{:.*}", prec, value)
+        }
+    }
+
+    /// Writes the same text as `format` straight into `writer`.
+    pub fn write<W: Write>(&self, writer: &mut W, value: f32) -> std::io::Result<()> {
+        match self {
+            Self::Shortest => write!(writer, "{}", value),
+            Self::Fixed(prec) => write!(writer, "{:.*}", prec, value)
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Value {
     LONG(i32),
@@ -134,33 +469,166 @@ impl Value {
         }
     }
 
+    pub fn get_field_type(&self) -> FieldType {
+        match self {
+            Self::LONG(_) => FieldType::LONG,
+            Self::STRING(_) => FieldType::STRING,
+            Self::FLOAT(_) => FieldType::FLOAT,
+            Self::ULONG(_) => FieldType::ULONG,
+            Self::SHORT(_) => FieldType::SHORT,
+            Self::CHAR(_) => FieldType::CHAR,
+            Self::STRINGOFF(_) => FieldType::STRINGOFF,
+            Self::NULL => FieldType::NULL
+        }
+    }
+
+    /// Returns the stored bit pattern widened to 64 bits: LONG is sign-extended, the other
+    /// integers are zero-extended, FLOAT gives its IEEE bits and STRINGOFF its offset.
+    /// STRING and NULL have no single bit pattern and always return 0.
+    pub fn raw_bits(&self) -> u64 {
+        match self {
+            Self::LONG(l) => *l as i64 as u64,
+            Self::FLOAT(f) => f.to_bits() as u64,
+            Self::ULONG(ul) => *ul as u64,
+            Self::SHORT(sh) => *sh as u64,
+            Self::CHAR(c) => *c as u64,
+            Self::STRINGOFF((off, _)) => *off as u64,
+            Self::STRING(_) | Self::NULL => 0
+        }
+    }
+
+    /// LONG as is; SHORT and CHAR widened as signed.
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Self::LONG(l) => Some(*l),
+            Self::SHORT(sh) => Some(*sh as i16 as i32),
+            Self::CHAR(c) => Some(*c as i8 as i32),
+            _ => None
+        }
+    }
+
+    /// ULONG as is; SHORT and CHAR widened as unsigned.
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Self::ULONG(ul) => Some(*ul),
+            Self::SHORT(sh) => Some(*sh as u32),
+            Self::CHAR(c) => Some(*c as u32),
+            _ => None
+        }
+    }
+
+    pub fn as_i16(&self) -> Option<i16> {
+        match self {
+            Self::SHORT(sh) => Some(*sh as i16),
+            Self::CHAR(c) => Some(*c as i8 as i16),
+            _ => None
+        }
+    }
+
+    pub fn as_i8(&self) -> Option<i8> {
+        match self {
+            Self::CHAR(c) => Some(*c as i8),
+            _ => None
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::FLOAT(f) => Some(*f),
+            _ => None
+        }
+    }
+
+    /// The text of a STRINGOFF, or of a STRING up to its first null if that is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::STRINGOFF((_, st)) => Some(st),
+            Self::STRING(s) => {
+                let end = s.iter().position(|x| *x == 0).unwrap_or(s.len());
+                std::str::from_utf8(&s[..end]).ok()
+            },
+            _ => None
+        }
+    }
+
+    /// Shifts an integer value into `field`'s packed position, erroring instead of silently
+    /// dropping bits that fall outside the field's mask. Non-integer values are returned as-is.
+    pub fn try_pack(&self, field: Field) -> Result<Value, BCSVError> {
+        let bits = match self {
+            Self::LONG(l) => *l as u32 as u64,
+            Self::ULONG(ul) => *ul as u64,
+            Self::SHORT(sh) => *sh as u64,
+            Self::CHAR(c) => *c as u64,
+            _ => return Ok(self.clone())
+        };
+        let mask = (field.mask & self.get_field_type().mask()) as u64;
+        let packed = match field.shift {
+            0..=31 => bits << field.shift,
+            _ => bits << 32
+        };
+        if packed & !mask != 0 {
+            return Err(format!("Value {} doesn't fit field 0x{:X} (mask 0x{:X}, shift {})",
+                self.get_string(false), field.hash, field.mask, field.shift).into());
+        }
+        Ok(match self {
+            Self::LONG(_) => Self::LONG(packed as u32 as i32),
+            Self::ULONG(_) => Self::ULONG(packed as u32),
+            Self::SHORT(_) => Self::SHORT(packed as u16),
+            _ => Self::CHAR(packed as u8)
+        })
+    }
+
+    /// Zeroes everything after the first null of a STRING value, which is how games store
+    /// fixed strings. `write` always does this on the bytes it emits.
+    pub fn normalize_string(&mut self) {
+        if let Self::STRING(s) = self {
+            null_pad(s);
+        }
+    }
+
     pub(crate) fn recalc(&mut self, field: Field) {
         match self {
             Self::LONG(lng) => {
-                *lng &= FieldType::LONG.mask() as i32;
-                *lng >>= field.shift as i32;
+                *lng = (*lng as u32 & field.mask).checked_shr(field.shift as u32).unwrap_or(0) as i32;
             },
             Self::ULONG(ulng) => {
-                *ulng &= FieldType::ULONG.mask();
-                *ulng >>= field.shift as u32;
+                *ulng = (*ulng & field.mask).checked_shr(field.shift as u32).unwrap_or(0);
             },
             Self::SHORT(ust) => {
-                *ust &= FieldType::SHORT.mask() as u16;
-                *ust >>= field.shift as u16;
+                *ust = (*ust & field.mask as u16).checked_shr(field.shift as u32).unwrap_or(0);
             },
             Self::CHAR(b) => {
-                *b &= FieldType::CHAR.mask() as u8;
-                *b >>= field.shift;
+                *b = (*b & field.mask as u8).checked_shr(field.shift as u32).unwrap_or(0);
             }
             _ => {}
         }
     }
 
+    /// Reads the cell at `row` of `field` from its absolute offset, so the reader can be
+    /// anywhere beforehand. The reader's position is restored afterwards.
     pub fn read<R: Read + Seek>(&mut self, reader: &mut R, endian: Endian,
         row: i64, header: Header, field: Field) -> BinResult<()> {
         let oldpos = reader.seek(SeekFrom::Current(0))?;
-        let off = row * header.entrysize as i64 + field.dataoff as i64;
-        reader.seek(SeekFrom::Current(off))?;
+        let off = (row as u64).checked_mul(header.entrysize as u64)
+            .and_then(|x| x.checked_add(header.entrydataoff as u64 + field.dataoff as u64));
+        let off = match off {
+            Some(off) if row >= 0 => off,
+            _ => {
+                let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                    format!("Row {} of field 0x{:X} is out of range", row, field.hash));
+                return Err(ioerr.into());
+            }
+        };
+        reader.seek(SeekFrom::Start(off))?;
+        self.read_raw(reader, endian, field)?;
+        reader.seek(SeekFrom::Start(oldpos))?;
+        self.calc_stringoff(reader, header)?;
+        Ok(())
+    }
+
+    /// Reads the value at the reader's position and unpacks it through `field`'s mask and
+    /// shift. STRINGOFF strings are left for the caller to resolve.
+    pub(crate) fn read_raw<R: Read + Seek>(&mut self, reader: &mut R, endian: Endian, field: Field) -> BinResult<()> {
         match self {
             Self::LONG(l) => {
                 *l = reader.read_type(endian)?;
@@ -185,12 +653,27 @@ impl Value {
             }
             Self::NULL => {},
         }
-        reader.seek(SeekFrom::Start(oldpos))?;
         self.recalc(field);
-        self.calc_stringoff(reader, header)?;
         Ok(())
     }
 
+    /// Looks up a STRINGOFF string in the already read string section `table`.
+    pub(crate) fn resolve_stringoff(&mut self, table: &[u8]) {
+        if let Self::STRINGOFF((n, str)) = self {
+            let bytes = table.get(*n as usize..).unwrap_or_default();
+            let bytes = match bytes.iter().position(|x| *x == 0) {
+                Some(end) => &bytes[..end],
+                // Same as `calc_stringoff`: an unterminated last string stops before the padding.
+                None => {
+                    let end = bytes.iter().rposition(|x| *x != 0x40).map_or(0, |x| x + 1);
+                    &bytes[..end]
+                }
+            };
+            let (dec, _, _) = SHIFT_JIS.decode(bytes);
+            *str = dec.into();
+        }
+    }
+
     pub(crate) fn calc_stringoff<R: Read + Seek>(&mut self, reader: &mut R, header: Header) -> BinResult<()> {
         if let Self::STRINGOFF((n, str)) = self {
             let stringoff = header.stringoffset();
@@ -198,10 +681,20 @@ impl Value {
             reader.seek(SeekFrom::Start(stringoff))?;
             reader.seek(SeekFrom::Current(*n as i64))?;
             let mut bytes = vec![0u8; 0];
-            let mut byte: u8 = reader.read_ne()?;
-            while byte != 0 {
-                bytes.push(byte);
-                byte = reader.read_ne()?;
+            let mut byte = [0u8];
+            loop {
+                // A last string missing its terminator runs into the 0x40 padding; stop at
+                // the end of the file and leave the padding out of the string.
+                if reader.read(&mut byte)? == 0 {
+                    while bytes.last() == Some(&0x40) {
+                        bytes.pop();
+                    }
+                    break;
+                }
+                if byte[0] == 0 {
+                    break;
+                }
+                bytes.push(byte[0]);
             }
             let (dec, _, _) = SHIFT_JIS.decode(&bytes);
             *str = dec.into();
@@ -211,15 +704,19 @@ impl Value {
     }
 
     pub fn get_string(&self, signed: bool) -> String {
+        self.get_string_with(signed, FloatFormat::default())
+    }
+
+    pub fn get_string_with(&self, signed: bool, float_format: FloatFormat) -> String {
         match self {
             Self::LONG(l) => {
                 format!("{}", l)
             },
             Self::STRING(s) => {
-                String::from(String::from_utf8_lossy(s))
+                fixed_string_text(s).into_owned()
             },
             Self::FLOAT(f) => {
-                format!("{}", f)
+                float_format.format(*f)
             },
             Self::ULONG(ul) => {
                 format!("{}", ul)
@@ -243,10 +740,40 @@ impl Value {
         }
     }
 
+    /// Writes the same text as `get_string` straight into `writer` without building a `String`.
+    pub fn write_text<W: Write>(&self, writer: &mut W, signed: bool) -> std::io::Result<()> {
+        self.write_text_with(writer, signed, FloatFormat::default())
+    }
+
+    /// Writes the same text as `get_string_with` straight into `writer`.
+    pub fn write_text_with<W: Write>(&self, writer: &mut W, signed: bool, float_format: FloatFormat)
+        -> std::io::Result<()> {
+        match self {
+            Self::LONG(l) => write!(writer, "{}", l),
+            Self::STRING(s) => writer.write_all(fixed_string_text(s).as_bytes()),
+            Self::FLOAT(f) => float_format.write(writer, *f),
+            Self::ULONG(ul) => write!(writer, "{}", ul),
+            Self::SHORT(sh) => match signed {
+                true => write!(writer, "{}", *sh as i16),
+                false => write!(writer, "{}", sh)
+            },
+            Self::CHAR(c) => match signed {
+                true => write!(writer, "{}", *c as i8),
+                false => write!(writer, "{}", c)
+            },
+            Self::STRINGOFF((_, st)) => writer.write_all(st.as_bytes()),
+            Self::NULL => writer.write_all(b"NULL")
+        }
+    }
+
     pub fn write<W: Write + Seek>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
         match self {
             Self::LONG(l) => writer.write_type(l, endian),
-            Self::STRING(s) => writer.write_ne(s),
+            Self::STRING(s) => {
+                let mut s = *s;
+                null_pad(&mut s);
+                writer.write_ne(&s)
+            },
             Self::FLOAT(f) => writer.write_type(f, endian),
             Self::ULONG(ul) => writer.write_type(ul, endian),
             Self::SHORT(sh) => writer.write_type(sh, endian),
@@ -255,79 +782,728 @@ impl Value {
             Self::NULL => Ok(())
         }
     }
+
+    /// Writes the value into `field`'s slot of an entry buffer. Integers are shifted and
+    /// masked into place and ORed with the slot, so packed fields sharing a `dataoff` keep
+    /// each other's bits; other types are written as is.
+    pub(crate) fn write_packed(&self, entry: &mut Cursor<&mut [u8]>, endian: Endian, field: Field) -> BinResult<()> {
+        let pos = field.dataoff as u64;
+        entry.set_position(pos);
+        let bits = (self.raw_bits() as u32).checked_shl(field.shift as u32).unwrap_or(0) & field.mask;
+        match self {
+            Self::LONG(_) | Self::ULONG(_) => {
+                let slot: u32 = entry.read_type(endian)?;
+                entry.set_position(pos);
+                entry.write_type(&(slot | bits), endian)
+            },
+            Self::SHORT(_) => {
+                let slot: u16 = entry.read_type(endian)?;
+                entry.set_position(pos);
+                entry.write_type(&(slot | bits as u16), endian)
+            },
+            Self::CHAR(_) => {
+                let slot: u8 = entry.read_ne()?;
+                entry.set_position(pos);
+                entry.write_ne(&(slot | bits as u8))
+            },
+            _ => self.write(entry, endian)
+        }
+    }
+}
+
+/// STRINGOFF values compare by text alone, since their offsets depend on the string table
+/// layout. STRING values compare up to the first null and FLOAT values by bit pattern.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::LONG(a), Self::LONG(b)) => a == b,
+            (Self::STRING(a), Self::STRING(b)) => {
+                let (mut a, mut b) = (*a, *b);
+                null_pad(&mut a);
+                null_pad(&mut b);
+                a == b
+            },
+            (Self::FLOAT(a), Self::FLOAT(b)) => a.to_bits() == b.to_bits(),
+            (Self::ULONG(a), Self::ULONG(b)) => a == b,
+            (Self::SHORT(a), Self::SHORT(b)) => a == b,
+            (Self::CHAR(a), Self::CHAR(b)) => a == b,
+            (Self::STRINGOFF((_, a)), Self::STRINGOFF((_, b))) => a == b,
+            (Self::NULL, Self::NULL) => true,
+            _ => false
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl std::hash::Hash for Value {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::STRING(s) => {
+                let mut s = *s;
+                null_pad(&mut s);
+                s.hash(state);
+            },
+            Self::STRINGOFF((_, st)) => st.hash(state),
+            _ => self.raw_bits().hash(state)
+        }
+    }
+}
+
+/// Formats like `get_string(true)`, reading SHORT and CHAR as signed. Use `get_string` to
+/// pick the signedness explicitly.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.get_string(true))
+    }
+}
+
+/// Hashes a field name, or parses it as a raw hash if it starts with `0x`.
+pub(crate) fn name_hash(name: &str) -> Result<u32, std::num::ParseIntError> {
+    match name.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => Ok(hash::calchash(name))
+    }
+}
+
+/// Encodes `text` as SHIFT_JIS into a null-padded STRING buffer, cut short at a character
+/// boundary so at least one terminating null remains.
+pub(crate) fn fixed_string(text: &str) -> Result<[u8; 32], BCSVError> {
+    let mut bytes = [0u8; 32];
+    let mut len = 0;
+    let mut buf = [0u8; 4];
+    for c in text.chars() {
+        let (data, _, had_errors) = SHIFT_JIS.encode(c.encode_utf8(&mut buf));
+        if had_errors {
+            return Err(format!("String \"{}\" can't be encoded as SHIFT_JIS", text).into());
+        }
+        if len + data.len() > 31 {
+            break;
+        }
+        bytes[len..len + data.len()].copy_from_slice(&data);
+        len += data.len();
+    }
+    Ok(bytes)
+}
+
+/// Decodes a STRING buffer as SHIFT_JIS up to its first null.
+fn fixed_string_text(bytes: &[u8; 32]) -> std::borrow::Cow<'_, str> {
+    let end = bytes.iter().position(|x| *x == 0).unwrap_or(bytes.len());
+    SHIFT_JIS.decode_without_bom_handling(&bytes[..end]).0
+}
+
+fn null_pad(bytes: &mut [u8; 32]) {
+    if let Some(end) = bytes.iter().position(|x| *x == 0) {
+        bytes[end..].fill(0);
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct BCSV {
     pub header: Header,
     pub fields: Vec<Field>,
-    pub(crate) values: Vec<Value>,
-    pub(crate) dictonary: HashMap<Field, Vec<Value>>
+    /// One column of values per field, in the same order as `fields`.
+    pub(crate) columns: Vec<Vec<Value>>,
+    /// The endian the table was last read with, used by `write_same_endian`.
+    pub endian: Option<Endian>,
+    /// Values for new cells keyed by field hash, used by `add_row`. Fields without one
+    /// (or whose default has the wrong type) fall back to `Value::new`.
+    pub defaults: HashMap<u32, Value>
 }
 
 impl BCSV {
+    /// An empty table whose header already describes zero fields, so it can be written as is.
     pub fn new() -> Self {
-        Self::default()
+        let mut result = Self::default();
+        result.header.entrydataoff = 16;
+        result
+    }
+
+    /// A small, fully laid out table with one field of every type and two rows.
+    pub fn sample() -> Self {
+        let columns = [
+            ("Id", [Value::LONG(0), Value::LONG(-1)]),
+            ("Name", [Value::STRING(fixed_string("Kuribo").expect("sample is valid")),
+                Value::STRING(fixed_string("Kinopio").expect("sample is valid"))]),
+            ("Scale", [Value::FLOAT(1.0), Value::FLOAT(0.5)]),
+            ("Flags", [Value::ULONG(0), Value::ULONG(0x80000000)]),
+            ("Count", [Value::SHORT(3), Value::SHORT(0xFFFF)]),
+            ("Type", [Value::CHAR(1), Value::CHAR(2)]),
+            ("ObjName", [Value::STRINGOFF((0, "Kuribo".into())), Value::STRINGOFF((0, "Kinopio".into()))])
+        ];
+        let mut builder = builder::BcsvBuilder::new();
+        for (name, column) in &columns {
+            builder = builder.add_field(*name, column[0].get_field_type());
+        }
+        for row in 0..2 {
+            builder = builder.push_row(columns.iter().map(|(_, column)| column[row].clone()).collect());
+        }
+        // The values above always match their fields.
+        builder.build().expect("sample is valid")
+    }
+
+    /// Builds a table from name-keyed records. Fields are taken from the first record,
+    /// sorted by name, with the type of each field given by its `Value` variant. Names
+    /// starting with `0x` are treated as raw hashes. Every record must have the same keys
+    /// and types.
+    pub fn from_records(records: &[HashMap<String, Value>]) -> Result<Self, BCSVError> {
+        let first = match records.first() {
+            Some(first) => first,
+            None => return Ok(Self::new())
+        };
+        let mut names = first.keys().collect::<Vec<_>>();
+        names.sort();
+        let mut builder = builder::BcsvBuilder::new();
+        for name in &names {
+            builder = builder.add_field(name.as_str(), first[*name].get_field_type());
+        }
+        for (i, record) in records.iter().enumerate() {
+            if record.len() != names.len() {
+                return Err(format!("Record {} has {} fields, expected {}", i, record.len(), names.len()).into());
+            }
+            let mut row = Vec::with_capacity(names.len());
+            for name in &names {
+                match record.get(*name) {
+                    Some(value) => row.push(value.clone()),
+                    None => return Err(format!("Record {} is missing field {}", i, name).into())
+                }
+            }
+            builder = builder.push_row(row);
+        }
+        builder.build()
+    }
+
+    /// Reassigns every field's `dataoff` in write order and sets `entrysize` to match.
+    /// Packable fields that already share a `dataoff` with the same type and disjoint masks
+    /// stay packed in one slot; everything else gets its own.
+    pub fn repair_offsets(&mut self) {
+        let mut doff = 0;
+        // (old dataoff, new dataoff, datatype, masks used so far)
+        let mut slots: Vec<(u16, u16, u8, u32)> = vec![];
+        for sorted in self.sort_fields() {
+            if let Some(field) = self.fields.iter_mut().find(|x| x.hash == sorted.hash) {
+                if field.is_packable() {
+                    let packed = slots.iter_mut().find(|x| x.0 == field.dataoff
+                        && x.2 == field.datatype && x.3 & field.mask == 0);
+                    if let Some(slot) = packed {
+                        slot.3 |= field.mask;
+                        field.dataoff = slot.1;
+                        continue;
+                    }
+                    slots.push((field.dataoff, doff, field.datatype, field.mask));
+                }
+                field.dataoff = doff;
+                doff += field.data_size();
+            }
+        }
+        self.header.entrysize = doff as u32;
+    }
+
+    /// Checks that the fields cover the entry without gaps or overlaps and end at `entrysize`.
+    /// Fields sharing a `dataoff` are accepted when they are all packable, have the same size
+    /// and have disjoint masks.
+    pub fn verify_offsets(&self) -> bool {
+        let mut fields = self.fields.clone();
+        fields.sort_by_key(|x| x.dataoff);
+        let mut end = 0u32;
+        let mut slot: Option<(u16, u16, u32, bool)> = None;
+        for field in &fields {
+            if let Some((off, size, mask, packable)) = slot.as_mut() {
+                if *off == field.dataoff {
+                    if !*packable || !field.is_packable() || *size != field.data_size()
+                        || *mask & field.mask != 0 {
+                        return false;
+                    }
+                    *mask |= field.mask;
+                    continue;
+                }
+            }
+            if field.dataoff as u32 != end {
+                return false;
+            }
+            slot = Some((field.dataoff, field.data_size(), field.mask, field.is_packable()));
+            end += field.data_size() as u32;
+        }
+        end == self.header.entrysize
+    }
+
+    /// Checks the header against the fields and columns, naming the first inconsistency.
+    /// Packed fields sharing a `dataoff` count once towards `entrysize`; fields that aren't
+    /// packable must have a `dataoff` of their own.
+    pub fn validate(&self) -> Result<(), BCSVError> {
+        let Header {entrycount, fieldcount, entrydataoff, entrysize} = self.header;
+        if fieldcount as usize != self.fields.len() {
+            return Err(format!("fieldcount is {} but there are {} fields", fieldcount, self.fields.len()).into());
+        }
+        let expected = 16 + 12 * self.fields.len() as u64;
+        if entrydataoff as u64 != expected {
+            return Err(format!("entrydataoff is 0x{:X}, expected 0x{:X}", entrydataoff, expected).into());
+        }
+        let mut seen = HashSet::new();
+        if let Some(field) = self.fields.iter().find(|x| !seen.insert(x.hash)) {
+            return Err(format!("Field 0x{:X} appears more than once", field.hash).into());
+        }
+        let sharing = |field: &Field| self.fields.iter().filter(|x| x.dataoff == field.dataoff).count() > 1;
+        if let Some(field) = self.fields.iter().find(|x| !x.is_packable() && sharing(x)) {
+            return Err(format!("Field 0x{:X} shares dataoff 0x{:X} but can't be packed",
+                field.hash, field.dataoff).into());
+        }
+        let mut offsets = HashSet::new();
+        let size: u32 = self.fields.iter().filter(|x| offsets.insert(x.dataoff))
+            .map(|x| x.data_size() as u32).sum();
+        if size != entrysize {
+            return Err(format!("entrysize is {} but the fields take {} bytes", entrysize, size).into());
+        }
+        for (i, field) in self.fields.iter().enumerate() {
+            let len = self.columns.get(i).map_or(0, |x| x.len());
+            if len != entrycount as usize {
+                return Err(format!("Field 0x{:X} has {} values but entrycount is {}", field.hash, len, entrycount).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns `dataoff` in write order and recomputes the header sizes and string offsets.
+    /// `entrycount` is left as is. Errors, changing nothing, if there are too many fields
+    /// for `entrydataoff`.
+    pub fn recompute_layout(&mut self) -> Result<(), BCSVError> {
+        let count = u32::try_from(self.fields.len())
+            .map_err(|_| format!("Field count {} overflows fieldcount", self.fields.len()))?;
+        self.header.set_field_count(count)?;
+        self.repair_offsets();
+        self.rebuild_string_table();
+        Ok(())
+    }
+
+    /// Reassigns every STRINGOFF offset from a fresh string table.
+    pub(crate) fn rebuild_string_table(&mut self) {
+        let table = self.string_table();
+        for value in self.columns.iter_mut().flatten() {
+            if let Value::STRINGOFF((off, str)) = value {
+                if let Some(o) = table.find(str) {
+                    *off = *o;
+                }
+            }
+        }
+    }
+
+    /// Number of rows every column has a value for, capped at `entrycount`.
+    fn complete_rows(&self) -> usize {
+        self.columns.iter().map(|x| x.len()).fold(self.header.entrycount as usize, usize::min)
+    }
+
+    /// Iterates the rows, each as its values in `fields` order.
+    pub fn rows(&self) -> impl Iterator<Item = Vec<&Value>> + '_ {
+        (0..self.complete_rows()).map(|row| self.columns.iter().map(|x| &x[row]).collect())
+    }
+
+    pub fn row(&self, index: usize) -> Option<Vec<&Value>> {
+        match index < self.complete_rows() {
+            true => Some(self.columns.iter().map(|x| &x[index]).collect()),
+            false => None
+        }
+    }
+
+    /// Sets `entrycount` to the number of complete rows (the length of the shortest column)
+    /// and rebuilds the string offsets. Call this after pushing whole rows through `extend`.
+    /// Returns the new entry count.
+    pub fn update_entry_count(&mut self) -> u32 {
+        let count = match self.fields.is_empty() {
+            true => 0,
+            false => self.columns.iter().map(|x| x.len()).min().unwrap_or(0)
+        };
+        self.header.entrycount = count as u32;
+        self.rebuild_string_table();
+        self.header.entrycount
+    }
+
+    /// Reads `count` fields from the reader's position. Each field is 12 bytes: hash, mask,
+    /// dataoff, shift and datatype. Only as many fields as the rest of the stream could hold
+    /// are reserved up front, so a bogus `count` fails on the first missing field instead of
+    /// exhausting memory.
+    pub fn read_fields<R: Read + Seek>(reader: &mut R, endian: Endian, count: u32) -> BinResult<Vec<Field>> {
+        let pos = reader.stream_position()?;
+        let remaining = reader.seek(SeekFrom::End(0))?.saturating_sub(pos);
+        reader.seek(SeekFrom::Start(pos))?;
+        let mut fields = Vec::with_capacity((count as u64).min(remaining / 12) as usize);
+        for _ in 0..count {
+            fields.push(reader.read_type(endian)?);
+        }
+        Ok(fields)
+    }
+
+    /// Writes `fields` in the same 12-byte layout `read_fields` expects.
+    pub fn write_fields<W: Write + Seek>(writer: &mut W, fields: &[Field], endian: Endian) -> BinResult<()> {
+        for field in fields {
+            writer.write_type(field, endian)?;
+        }
+        Ok(())
+    }
+
+    pub fn from_reader<R: Read + Seek>(reader: &mut R, endian: Endian) -> BinResult<BCSV> {
+        let mut result = Self::new();
+        result.read(reader, endian)?;
+        Ok(result)
+    }
+
+    pub fn from_bytes<A: AsRef<[u8]>>(data: A, endian: Endian) -> BinResult<BCSV> {
+        Self::from_reader(&mut Cursor::new(data.as_ref()), endian)
+    }
+
+    /// Reads a file of unknown endian, picking the one whose header passes
+    /// `Header::is_consistent` for the rest of the stream, the same check `read` makes.
+    /// Big endian wins if both look plausible.
+    pub fn read_auto<R: Read + Seek>(reader: &mut R) -> BinResult<(BCSV, Endian)> {
+        let start = reader.stream_position()?;
+        let len = reader.seek(SeekFrom::End(0))? - start;
+        for endian in [Endian::Big, Endian::Little] {
+            reader.seek(SeekFrom::Start(start))?;
+            let header: Header = reader.read_type(endian)?;
+            if header.is_consistent(len) {
+                reader.seek(SeekFrom::Start(start))?;
+                return Ok((Self::from_reader(reader, endian)?, endian));
+            }
+        }
+        let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidData,
+            "Header isn't plausible in either endian");
+        Err(ioerr.into())
+    }
+
+    /// Serializes just the header and field table, big endian, as a compact schema blob.
+    pub fn export_schema_bytes(&self) -> Vec<u8> {
+        let mut stream = Cursor::new(vec![]);
+        // Writing into memory can't fail.
+        stream.write_type(&self.header, Endian::Big).unwrap_or_default();
+        Self::write_fields(&mut stream, &self.fields, Endian::Big).unwrap_or_default();
+        stream.into_inner()
+    }
+
+    /// Builds an empty table with the header and fields of an `export_schema_bytes` blob,
+    /// ready to have rows added.
+    pub fn import_schema_bytes(data: &[u8]) -> Result<BCSV, BCSVError> {
+        let mut reader = Cursor::new(data);
+        let mut result = Self::new();
+        result.header = reader.read_type(Endian::Big)?;
+        let expected = 16 + 12 * result.header.fieldcount as u64;
+        if data.len() as u64 != expected {
+            return Err(format!("Schema has {} bytes, expected {} for {} fields",
+                data.len(), expected, result.header.fieldcount).into());
+        }
+        result.fields = Self::read_fields(&mut reader, Endian::Big, result.header.fieldcount)?;
+        result.columns = vec![vec![]; result.fields.len()];
+        result.header.entrycount = 0;
+        Ok(result)
+    }
+
+    pub fn read_from_path<P: AsRef<Path>>(path: P, endian: Endian) -> Result<BCSV, BCSVError> {
+        let mut reader = BufReader::new(std::fs::File::open(path)?);
+        Ok(Self::from_reader(&mut reader, endian)?)
+    }
+
+    /// Writes to `path`, replacing any existing file.
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P, endian: Endian) -> Result<(), BCSVError> {
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut writer, endian)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Errors on a field whose datatype byte isn't a known `FieldType`, since its values
+    /// can't be read and would be lost on write, and on two fields sharing a hash, since
+    /// lookups by field would only ever see the first.
+    fn check_fields(fields: &[Field]) -> BinResult<()> {
+        if let Some(field) = fields.iter().find(|x| x.datatype > FieldType::NULL as u8) {
+            let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("Field 0x{:X} has unknown datatype {}", field.hash, field.datatype));
+            return Err(ioerr.into());
+        }
+        let integer = |x: &Field| matches!(x.get_field_type(),
+            FieldType::LONG | FieldType::ULONG | FieldType::SHORT | FieldType::CHAR);
+        if let Some(field) = fields.iter().find(|x| integer(x) && x.shift as u16 >= 8 * x.data_size()) {
+            let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("Field 0x{:X} has shift {} past its {}-bit type", field.hash, field.shift, 8 * field.data_size()));
+            return Err(ioerr.into());
+        }
+        let mut seen = HashSet::new();
+        if let Some(field) = fields.iter().find(|x| !seen.insert(x.hash)) {
+            let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("Field 0x{:X} appears more than once", field.hash));
+            return Err(ioerr.into());
+        }
+        Ok(())
+    }
+
+    /// Errors before anything is allocated if `header` can't describe the stream behind
+    /// `reader`, leaving the reader where it was.
+    fn check_header<R: Read + Seek>(reader: &mut R, header: Header) -> BinResult<()> {
+        let pos = reader.stream_position()?;
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(pos))?;
+        if !header.is_consistent(file_len) {
+            let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidData,
+                format!("Header {:?} doesn't fit a file of 0x{:X} bytes", header, file_len));
+            return Err(ioerr.into());
+        }
+        Ok(())
     }
 
     pub fn read<R: Read + Seek>(&mut self, reader: &mut R, endian: Endian) -> BinResult<()> {
-        let Self {header, fields, values, dictonary} = self;
+        let Self {header, fields, columns, ..} = self;
         *header = reader.read_type(endian)?;
-        *fields = vec![Field::default(); header.fieldcount as usize];
-        for field in fields.iter_mut() {
-            *field = reader.read_type(endian)?;
-            dictonary.insert(*field, vec![]);
-        }
+        Self::check_header(reader, *header)?;
+        *fields = Self::read_fields(reader, endian, header.fieldcount)?;
+        Self::check_fields(fields)?;
+        *columns = vec![vec![]; fields.len()];
+        // Read the entries and the string section in one go each, then slice the cells out
+        // of memory instead of seeking for every value.
         reader.seek(SeekFrom::Start(header.entrydataoff as u64))?;
-        let entrysize = header.entrycount as usize * fields.len();
-        let mut v = 0;
-        let mut row = 0;
-        while v != entrysize {
-            if v >= entrysize {
-                break;
-            }
-            for field in fields.iter() {
+        let len = header.entrycount as u64 * header.entrysize as u64;
+        let mut entries = vec![];
+        reader.take(len).read_to_end(&mut entries)?;
+        if (entries.len() as u64) < len {
+            let ioerr = std::io::Error::new(std::io::ErrorKind::UnexpectedEof,
+                format!("Entry data ends after 0x{:X} of 0x{:X} bytes", entries.len(), len));
+            return Err(ioerr.into());
+        }
+        let mut strings = vec![];
+        reader.read_to_end(&mut strings)?;
+        let mut cursor = Cursor::new(&entries[..]);
+        for row in 0..header.entrycount as u64 {
+            for (field, column) in fields.iter().zip(columns.iter_mut()) {
                 let mut value = Value::new(*field);
-                value.read(reader, endian, row, *header, *field)?;
-                values.push(value.clone());
-                if let Some(entries) = dictonary.get_mut(field) {
-                    entries.push(value);
+                cursor.set_position(row * header.entrysize as u64 + field.dataoff as u64);
+                value.read_raw(&mut cursor, endian, *field)?;
+                value.resolve_stringoff(&strings);
+                column.push(value);
+            }
+        }
+        self.endian = Some(endian);
+        Ok(())
+    }
+
+    /// Checks that the entries and every STRINGOFF string, including its terminator, end
+    /// within a file of `file_len` bytes. Meant for after `read`, while the offsets are still
+    /// the ones from the file.
+    pub fn validate_against_file_len(&self, file_len: u64) -> Result<(), BCSVError> {
+        let stringoff = self.header.stringoffset();
+        if stringoff > file_len {
+            return Err(format!("String table starts at 0x{:X}, past the end of the file (0x{:X})",
+                stringoff, file_len).into());
+        }
+        for value in self.columns.iter().flatten() {
+            if let Value::STRINGOFF((off, str)) = value {
+                let end = stringoff + *off as u64 + SHIFT_JIS.encode(str).0.len() as u64 + 1;
+                if end > file_len {
+                    return Err(format!("String \"{}\" ends at 0x{:X}, past the end of the file (0x{:X})",
+                        str, end, file_len).into());
                 }
-                v += 1;
             }
-            row += 1;
         }
         Ok(())
     }
 
+    /// Buffers the whole of an async source into memory, then reads it like `read`.
+    #[cfg(feature = "async")]
+    pub async fn read_async<R: tokio::io::AsyncRead + Unpin>(&mut self, reader: &mut R, endian: Endian) -> BinResult<()> {
+        use tokio::io::AsyncReadExt;
+        let mut data = vec![];
+        reader.read_to_end(&mut data).await?;
+        self.read(&mut Cursor::new(data), endian)
+    }
+
+    /// Reads the header and fields, then only the listed rows in the given order, giving a
+    /// table with one entry per index in `rows`.
+    pub fn read_rows<R: Read + Seek>(reader: &mut R, endian: Endian, rows: &[usize]) -> BinResult<BCSV> {
+        let mut result = Self::new();
+        result.header = reader.read_type(endian)?;
+        Self::check_header(reader, result.header)?;
+        result.fields = Self::read_fields(reader, endian, result.header.fieldcount)?;
+        Self::check_fields(&result.fields)?;
+        result.columns = vec![Vec::with_capacity(rows.len()); result.fields.len()];
+        for row in rows {
+            if *row >= result.header.entrycount as usize {
+                let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                    format!("Row {} is out of range for {} entries", row, result.header.entrycount));
+                return Err(ioerr.into());
+            }
+            for (field, column) in result.fields.iter().zip(result.columns.iter_mut()) {
+                let mut value = Value::new(*field);
+                value.read(reader, endian, *row as i64, result.header, *field)?;
+                column.push(value);
+            }
+        }
+        result.header.entrycount = rows.len() as u32;
+        result.rebuild_string_table();
+        result.endian = Some(endian);
+        Ok(result)
+    }
+
     pub fn convert_to_csv(&self, hashes: &HashMap<u32, String>, signed: bool, delim: char) -> String {
-        let mut result = String::new();
-        for i in 0..self.fields.len() {
-            let last = i == self.fields.len() - 1;
+        self.convert_to_csv_with(hashes, signed, delim, FloatFormat::default())
+    }
+
+    pub fn convert_to_csv_with(&self, hashes: &HashMap<u32, String>, signed: bool, delim: char,
+        float_format: FloatFormat) -> String {
+        let columns = (0..self.fields.len()).collect::<Vec<_>>();
+        self.csv_text(&columns, hashes, signed, delim, float_format)
+    }
+
+    /// Like `convert_to_csv`, but only emits `fields`, in the order given. Errors if the
+    /// table has no field with one of the requested hashes.
+    pub fn convert_to_csv_columns(&self, fields: &[Field], hashes: &HashMap<u32, String>, signed: bool,
+        delim: char) -> Result<String, BCSVError> {
+        self.convert_to_csv_columns_with(fields, hashes, signed, delim, FloatFormat::default())
+    }
+
+    pub fn convert_to_csv_columns_with(&self, fields: &[Field], hashes: &HashMap<u32, String>,
+        signed: bool, delim: char, float_format: FloatFormat) -> Result<String, BCSVError> {
+        let mut columns = vec![];
+        for field in fields {
+            match self.fields.iter().position(|x| x.hash == field.hash) {
+                Some(i) => columns.push(i),
+                None => return Err(format!("Field 0x{:X} is not in the table", field.hash).into())
+            }
+        }
+        Ok(self.csv_text(&columns, hashes, signed, delim, float_format))
+    }
+
+    /// Streams the same text as `convert_to_csv` into `writer`, one cell at a time, instead
+    /// of building it in memory first.
+    pub fn to_csv_writer<W: Write>(&self, writer: &mut W, hashes: &HashMap<u32, String>, signed: bool,
+        delim: char) -> Result<(), BCSVError> {
+        self.to_csv_writer_with(writer, hashes, signed, delim, FloatFormat::default())
+    }
+
+    pub fn to_csv_writer_with<W: Write>(&self, writer: &mut W, hashes: &HashMap<u32, String>,
+        signed: bool, delim: char, float_format: FloatFormat) -> Result<(), BCSVError> {
+        let columns = (0..self.fields.len()).collect::<Vec<_>>();
+        Ok(self.write_csv_text(writer, &columns, hashes, signed, delim, float_format)?)
+    }
+
+    fn csv_text(&self, columns: &[usize], hashes: &HashMap<u32, String>, signed: bool, delim: char,
+        float_format: FloatFormat) -> String {
+        let mut result = vec![];
+        // Writing into memory can't fail, and every piece written is a `String`.
+        self.write_csv_text(&mut result, columns, hashes, signed, delim, float_format).unwrap_or_default();
+        String::from_utf8(result).unwrap_or_default()
+    }
+
+    fn write_csv_text<W: Write>(&self, writer: &mut W, columns: &[usize], hashes: &HashMap<u32, String>,
+        signed: bool, delim: char, float_format: FloatFormat) -> std::io::Result<()> {
+        for (i, col) in columns.iter().enumerate() {
+            let last = i == columns.len() - 1;
             let term = match last { true => '\n', false => delim };
-            result += &format!("{}:{}{}", self.fields[i].get_name(hashes), self.fields[i].datatype, term);
+            write!(writer, "{}{}", self.fields[*col].to_descriptor(hashes, delim), term)?;
         }
-        let mut v = 0;
-        while v < self.values.len() {
-            for i in 0..self.fields.len() {
-                let last = i == self.fields.len() - 1;
+        if self.fields.is_empty() {
+            return Ok(());
+        }
+        for row in 0..self.complete_rows() {
+            for (i, col) in columns.iter().enumerate() {
+                let last = i == columns.len() - 1;
                 let term = match last { false => delim, true => '\n' };
-                result += &format!("{}{}", self.values[v].get_string(signed), term);
-                v += 1;
+                write!(writer, "{}{}", self.columns[*col][row].get_string_with(signed, float_format), term)?;
             }
         }
+        Ok(())
+    }
+
+    /// Lazily yields the CSV header line and then one line per row, without line breaks.
+    /// Joining the lines with `\n` gives the same text as `convert_to_csv`, so a table with
+    /// no fields yields nothing.
+    pub fn csv_lines<'a>(&'a self, hashes: &'a HashMap<u32, String>, signed: bool, delim: char)
+        -> impl Iterator<Item = String> + 'a {
+        self.csv_lines_with(hashes, signed, delim, FloatFormat::default())
+    }
+
+    pub fn csv_lines_with<'a>(&'a self, hashes: &'a HashMap<u32, String>, signed: bool, delim: char,
+        float_format: FloatFormat) -> impl Iterator<Item = String> + 'a {
+        let join = move |cells: Vec<String>| cells.join(&delim.to_string());
+        let header = self.fields.iter().map(|x| x.to_descriptor(hashes, delim)).collect();
+        let rows = self.rows().map(move |row| join(row.iter().map(|x| x.get_string_with(signed, float_format)).collect()));
+        let lines = match self.fields.is_empty() { true => 0, false => usize::MAX };
+        std::iter::once(join(header)).chain(rows).take(lines)
+    }
+
+    /// Writes the table as CSV records into `writer`, reusing one scratch buffer for every
+    /// value instead of allocating a `String` per cell.
+    pub fn write_csv<W: Write>(&self, writer: &mut csv::Writer<W>, hashes: &HashMap<u32, String>, signed: bool) -> Result<(), BCSVError> {
+        self.write_csv_with(writer, hashes, signed, FloatFormat::default())
+    }
+
+    pub fn write_csv_with<W: Write>(&self, writer: &mut csv::Writer<W>, hashes: &HashMap<u32, String>,
+        signed: bool, float_format: FloatFormat) -> Result<(), BCSVError> {
+        let mut buf = Vec::new();
+        for field in &self.fields {
+            writer.write_field(field.to_descriptor(hashes, ','))?;
+        }
+        writer.write_record(None::<&[u8]>)?;
+        if self.fields.is_empty() {
+            return Ok(());
+        }
+        for row in self.rows() {
+            for value in row {
+                buf.clear();
+                value.write_text_with(&mut buf, signed, float_format)?;
+                writer.write_field(&buf)?;
+            }
+            writer.write_record(None::<&[u8]>)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Renders the table with aligned columns for terminal display. Headers show the resolved
+    /// name and type, cells longer than 24 characters are cut short, and only the first
+    /// `max_rows` rows are shown when it is set.
+    pub fn pretty_print(&self, hashes: &HashMap<u32, String>, signed: bool, max_rows: Option<usize>) -> String {
+        const MAX_WIDTH: usize = 24;
+        let clip = |text: String| match text.chars().count() > MAX_WIDTH {
+            true => text.chars().take(MAX_WIDTH - 3).collect::<String>() + "...",
+            false => text
+        };
+        let rows = self.complete_rows();
+        let shown = max_rows.map_or(rows, |x| x.min(rows));
+        let mut cells = vec![self.fields.iter()
+            .map(|x| clip(format!("{}:{:?}", x.get_name(hashes), x.get_field_type())))
+            .collect::<Vec<_>>()];
+        for row in self.rows().take(shown) {
+            cells.push(row.iter().map(|x| clip(x.get_string(signed))).collect());
+        }
+        let mut widths = vec![0; self.fields.len()];
+        for line in &cells {
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let mut result = String::new();
+        for (i, line) in cells.iter().enumerate() {
+            let padded = line.iter().zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>();
+            result += padded.join(" | ").trim_end();
+            result.push('\n');
+            if i == 0 {
+                let rule = widths.iter().map(|x| "-".repeat(*x)).collect::<Vec<_>>();
+                result += &rule.join("-+-");
+                result.push('\n');
+            }
+        }
+        if shown < rows {
+            result += &format!("... {} more rows\n", rows - shown);
+        }
         result
     }
 
-    pub fn convert_to_xlsx<S: AsRef<str>>(&self, name: S, hashes: &HashMap<u32, String>, signed: bool) -> Result<(), BcsvError> {
+    pub fn convert_to_xlsx<S: AsRef<str>>(&self, name: S, hashes: &HashMap<u32, String>, signed: bool) -> Result<(), BCSVError> {
         let book = xlsxwriter::Workbook::new(name.as_ref())?;
         let mut sheet = book.add_worksheet(None)?;
         for i in 0..self.fields.len() {
-            let text = format!("{}:{}", self.fields[i].get_name(hashes), self.fields[i].datatype);
+            let text = self.fields[i].to_descriptor(hashes, ',');
             sheet.write_string(0 as u32, i as u16, &text, None)?;
         }
-        for i in 0..self.fields.len() {
-            let values = &self.dictonary[&self.fields[i]];
+        for (i, values) in self.columns.iter().enumerate() {
             for j in 0..values.len() {
                 sheet.write_string((j + 1) as u32, i as u16, &values[j].get_string(signed), None)?;
             }
@@ -350,58 +1526,492 @@ impl BCSV {
         result
     }
 
+    /// Position of the field with the same hash as `field` in `self.fields`.
+    pub fn field_index(&self, field: Field) -> Option<usize> {
+        self.fields.iter().position(|x| x.hash == field.hash)
+    }
+
+    /// Borrows the column of the field called `name`, which may also be a raw `0x` hash.
+    pub fn get_by_name(&self, name: &str) -> Option<&Vec<Value>> {
+        let hash = name_hash(name).ok()?;
+        let index = self.fields.iter().position(|x| x.hash == hash)?;
+        self.columns.get(index)
+    }
+
+    pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut Vec<Value>> {
+        let hash = name_hash(name).ok()?;
+        let index = self.fields.iter().position(|x| x.hash == hash)?;
+        self.columns.get_mut(index)
+    }
+
+    /// Number of distinct values in the column of the field with `field`'s hash, or `None` if
+    /// the table has no such field.
+    pub fn count_distinct(&self, field: Field) -> Option<usize> {
+        let column = self.columns.get(self.field_index(field)?)?;
+        Some(column.iter().collect::<std::collections::HashSet<_>>().len())
+    }
+
+    /// Counts how often each value occurs in an integer column, with SHORT and CHAR values
+    /// taken as unsigned. `None` if the field is missing or isn't an integer type.
+    pub fn value_histogram(&self, field: Field) -> Option<HashMap<i64, usize>> {
+        let index = self.field_index(field)?;
+        self.fields[index].get_field_type().value_range(false)?;
+        let mut result = HashMap::new();
+        for value in self.columns.get(index)? {
+            let key = match value {
+                Value::LONG(l) => *l as i64,
+                _ => value.raw_bits() as i64
+            };
+            *result.entry(key).or_insert(0) += 1;
+        }
+        Some(result)
+    }
+
+    /// Borrows every column keyed by its field name from `hashes`. Unnamed fields use their
+    /// `0x` hash as the key; when two fields resolve to the same name the first one is kept.
+    pub fn columns_by_name(&self, hashes: &HashMap<u32, String>) -> HashMap<String, &Vec<Value>> {
+        let mut result = HashMap::new();
+        for (field, column) in self.fields.iter().zip(&self.columns) {
+            result.entry(field.get_name(hashes)).or_insert(column);
+        }
+        result
+    }
+
+    /// Hashes of the fields with no name in `hashes`, in field order.
+    pub fn unknown_hashes(&self, hashes: &HashMap<u32, String>) -> Vec<u32> {
+        self.fields.iter().map(|x| x.hash).filter(|x| !hashes.contains_key(x)).collect()
+    }
+
+    /// Hashes every candidate name and adds those matching an unknown field to `hashes`.
+    pub fn resolve_names(&self, hashes: &mut HashMap<u32, String>, candidates: &[&str]) {
+        let unknown = self.unknown_hashes(hashes);
+        for name in candidates {
+            let hash = hash::calchash(name);
+            if unknown.contains(&hash) {
+                hashes.entry(hash).or_insert_with(|| name.to_string());
+            }
+        }
+    }
+
+    /// Removes the field with `field`'s hash and returns its column, then recomputes the
+    /// remaining offsets, `entrysize`, `fieldcount` and `entrydataoff`. `Ok(None)` if no
+    /// field has that hash.
+    pub fn remove_field(&mut self, field: Field) -> Result<Option<Vec<Value>>, BCSVError> {
+        let index = match self.field_index(field) {
+            Some(index) => index,
+            None => return Ok(None)
+        };
+        self.fields.remove(index);
+        let column = match index < self.columns.len() {
+            true => self.columns.remove(index),
+            false => vec![]
+        };
+        self.recompute_layout()?;
+        Ok(Some(column))
+    }
+
+    /// Renames the field hashed `old_hash` to `new_name`, keeping its column and position.
+    pub fn rename_by_hash(&mut self, old_hash: u32, new_name: &str) -> Result<(), BCSVError> {
+        let index = match self.fields.iter().position(|x| x.hash == old_hash) {
+            Some(index) => index,
+            None => return Err(format!("No field has hash 0x{:X}", old_hash).into())
+        };
+        let new_hash = hash::calchash(new_name);
+        if new_hash != old_hash && self.fields.iter().any(|x| x.hash == new_hash) {
+            return Err(format!("\"{}\" (0x{:X}) collides with an existing field", new_name, new_hash).into());
+        }
+        self.fields[index].hash = new_hash;
+        Ok(())
+    }
+
+    fn check_row(&self, index: usize) -> Result<(), BCSVError> {
+        if index >= self.header.entrycount as usize {
+            return Err(format!("Row {} is out of range for {} entries", index, self.header.entrycount).into());
+        }
+        Ok(())
+    }
+
+    /// Swaps two rows across every column.
+    pub fn swap_rows(&mut self, a: usize, b: usize) -> Result<(), BCSVError> {
+        self.check_row(a)?;
+        self.check_row(b)?;
+        for column in self.columns.iter_mut() {
+            if a < column.len() && b < column.len() {
+                column.swap(a, b);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a row from every column, decrements `entrycount` and rebuilds the string offsets.
+    pub fn remove_row(&mut self, index: usize) -> Result<(), BCSVError> {
+        self.check_row(index)?;
+        for column in self.columns.iter_mut() {
+            if index < column.len() {
+                column.remove(index);
+            }
+        }
+        self.header.entrycount -= 1;
+        self.rebuild_string_table();
+        Ok(())
+    }
+
+    /// Inserts `row` before `index`, or appends it when `index` is `entrycount`. The values
+    /// must match the field types in `fields` order.
+    pub fn insert_row(&mut self, index: usize, row: Vec<Value>) -> Result<(), BCSVError> {
+        if index > self.header.entrycount as usize {
+            return Err(format!("Row {} is out of range for {} entries", index, self.header.entrycount).into());
+        }
+        if row.len() != self.fields.len() {
+            return Err(format!("Row has {} values, expected {}", row.len(), self.fields.len()).into());
+        }
+        for (field, value) in self.fields.iter().zip(&row) {
+            if value.get_field_type() != field.get_field_type() {
+                return Err(format!("Value {:?} doesn't match the type {:?} of field 0x{:X}",
+                    value, field.get_field_type(), field.hash).into());
+            }
+        }
+        self.columns.resize_with(self.fields.len(), Vec::new);
+        for (column, value) in self.columns.iter_mut().zip(row) {
+            column.insert(index.min(column.len()), value);
+        }
+        self.header.entrycount += 1;
+        self.rebuild_string_table();
+        Ok(())
+    }
+
+    /// The value new cells of `field` start with.
+    pub fn default_value(&self, field: Field) -> Value {
+        match self.defaults.get(&field.hash) {
+            Some(value) if value.get_field_type() == field.get_field_type() => value.clone(),
+            _ => Value::new(field)
+        }
+    }
+
+    /// Appends a row filled with each field's default value and returns its index.
+    pub fn add_row(&mut self) -> usize {
+        let row = self.fields.iter().map(|x| self.default_value(*x)).collect::<Vec<_>>();
+        self.columns.resize_with(self.fields.len(), Vec::new);
+        for (column, value) in self.columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+        self.header.entrycount += 1;
+        self.rebuild_string_table();
+        self.header.entrycount as usize - 1
+    }
+
+    /// Replaces every field's hash with `f(hash)`, keeping each column with its field. Errors
+    /// without changing anything if two fields would end up with the same hash.
+    pub fn map_field_hashes<F: Fn(u32) -> u32>(&mut self, f: F) -> Result<(), BCSVError> {
+        let hashes = self.fields.iter().map(|x| f(x.hash)).collect::<Vec<_>>();
+        for (i, hash) in hashes.iter().enumerate() {
+            if hashes[..i].contains(hash) {
+                return Err(format!("Several fields map to hash 0x{:X}", hash).into());
+            }
+        }
+        for (field, hash) in self.fields.iter_mut().zip(hashes) {
+            field.hash = hash;
+        }
+        Ok(())
+    }
+
+    /// Removes every row identical to an earlier one across all fields, keeping the first,
+    /// and rebuilds the string offsets. Returns the number of rows removed.
+    pub fn dedup_rows(&mut self) -> usize {
+        let keep = {
+            let mut seen = std::collections::HashSet::new();
+            (0..self.header.entrycount as usize).map(|row| {
+                let key = self.columns.iter().map(|column| column.get(row)).collect::<Vec<_>>();
+                seen.insert(key)
+            }).collect::<Vec<_>>()
+        };
+        let removed = keep.iter().filter(|x| !**x).count();
+        if removed == 0 {
+            return 0;
+        }
+        for column in self.columns.iter_mut() {
+            let mut row = 0;
+            column.retain(|_| {
+                row += 1;
+                keep.get(row - 1).copied().unwrap_or(true)
+            });
+        }
+        self.header.entrycount -= removed as u32;
+        self.rebuild_string_table();
+        removed
+    }
+
+    /// Returns the absolute file offset and size of the cell at `row` in `field`, or `None`
+    /// if the row is out of range or the table has no field with that hash.
+    pub fn cell_offset(&self, row: usize, field: Field) -> Option<(u64, u16)> {
+        if row >= self.header.entrycount as usize {
+            return None;
+        }
+        let field = self.fields.iter().find(|x| x.hash == field.hash)?;
+        let off = self.header.entrydataoff as u64 + row as u64 * self.header.entrysize as u64
+            + field.dataoff as u64;
+        Some((off, field.data_size()))
+    }
+
+    /// Compares each field's type against `expected`, returning `(hash, expected, actual)`
+    /// for every mismatch. Fields missing from `expected` are not checked.
+    pub fn check_types(&self, expected: &HashMap<u32, FieldType>) -> Vec<(u32, FieldType, FieldType)> {
+        self.fields.iter().filter_map(|field| {
+            let actual = field.get_field_type();
+            match expected.get(&field.hash) {
+                Some(exp) if *exp != actual => Some((field.hash, *exp, actual)),
+                _ => None
+            }
+        }).collect()
+    }
+
+    pub fn with_endian(mut self, endian: Endian) -> Self {
+        self.endian = Some(endian);
+        self
+    }
+
+    /// Writes with the endian recorded by `read` (or `with_endian`), erroring if none is known.
+    pub fn write_same_endian<W: Write + Seek>(&self, writer: &mut W) -> BinResult<()> {
+        match self.endian {
+            Some(endian) => self.write(writer, endian),
+            None => {
+                let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                    "No endian was recorded for this BCSV");
+                Err(ioerr.into())
+            }
+        }
+    }
+
+    /// Writes the table, then reads the written bytes back and errors if they don't compare
+    /// equal to `self`. Like `write`, this expects the writer to start at offset 0.
+    pub fn write_verified<W: Read + Write + Seek>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        self.write(writer, endian)?;
+        writer.seek(SeekFrom::Start(0))?;
+        let mut reread = Self::new();
+        reread.read(writer, endian)?;
+        writer.seek(SeekFrom::End(0))?;
+        if reread != *self {
+            let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidData,
+                "Written BCSV doesn't read back identically");
+            return Err(ioerr.into());
+        }
+        Ok(())
+    }
+
+    /// Writes the entries with STRINGOFF offsets taken from a fresh table of the strings in
+    /// row order, then writes that table sequentially as the string section.
     pub fn write<W: Write + Seek>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        self.write_with_string_table(writer, endian, &self.string_table())
+    }
+
+    /// Like `write`, but pads the end of the string section with `pad_byte` instead of
+    /// `0x40`, e.g. `0x00` for files from tools that pad with zeroes.
+    pub fn write_with_options<W: Write + Seek>(&self, writer: &mut W, endian: Endian,
+        pad_byte: u8) -> BinResult<()> {
+        self.write_padded(writer, endian, &self.string_table(), pad_byte)
+    }
+
+    /// The deduplicated STRINGOFF strings in the order they first appear in the rows.
+    fn string_table(&self) -> string_table::StringTable {
+        let mut table = string_table::StringTable::new();
+        let rows = self.columns.iter().map(|x| x.len()).max().unwrap_or(0);
+        for row in 0..rows {
+            for column in &self.columns {
+                if let Some(Value::STRINGOFF((_, str))) = column.get(row) {
+                    table.push(str);
+                }
+            }
+        }
+        table
+    }
+
+    /// Writes the table using the offsets of a caller-supplied string table, for example one
+    /// shared between several files, and emits that table as the string section. Every
+    /// STRINGOFF string must already be present in `table`.
+    pub fn write_with_string_table<W: Write + Seek>(&self, writer: &mut W, endian: Endian,
+        table: &string_table::StringTable) -> BinResult<()> {
+        self.write_padded(writer, endian, table, 0x40)
+    }
+
+    fn write_padded<W: Write + Seek>(&self, writer: &mut W, endian: Endian,
+        table: &string_table::StringTable, pad_byte: u8) -> BinResult<()> {
+        let mut bcsv = self.clone();
+        for value in bcsv.columns.iter_mut().flatten() {
+            if let Value::STRINGOFF((off, str)) = value {
+                match table.find(str) {
+                    Some(o) => *off = *o,
+                    None => {
+                        let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                            format!("String \"{}\" is missing from the string table", str));
+                        return Err(ioerr.into());
+                    }
+                }
+            }
+        }
+        bcsv.write_entries(writer, endian)?;
+        Self::write_strings(writer, &table.build_bytes()?, pad_byte)
+    }
+
+    fn write_entries<W: Write + Seek>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        for (i, field) in self.fields.iter().enumerate() {
+            let len = self.columns.get(i).map_or(0, |column| column.len());
+            if len != self.header.entrycount as usize {
+                let ioerr = std::io::Error::new(std::io::ErrorKind::InvalidData,
+                    format!("Field 0x{:X} has {} values but entrycount is {}", field.hash, len, self.header.entrycount));
+                return Err(ioerr.into());
+            }
+        }
         {
             let Self {header, fields, ..} = self;
             writer.write_type(header, endian)?;
-            for field in fields {
-                writer.write_type(field, endian)?;
-            }
-        }
-        let mut v = 0;
-        let mut dict = self.dictonary.clone();
-        let sorted = self.sort_fields();
-        while v != self.values.len() {
-            if v >= self.values.len() {
-                break;
-            }
-            for f in &sorted {
-                if let Some(vals) = dict.get_mut(f) {
-                    vals[0].write(writer, endian)?;
-                    vals.remove(0);
-                    v += 1;
-                }
+            Self::write_fields(writer, fields, endian)?;
+        }
+        let mut entry = vec![0u8; self.header.entrysize as usize];
+        for row in 0..self.header.entrycount as usize {
+            entry.fill(0);
+            let mut cursor = Cursor::new(&mut entry[..]);
+            for (field, column) in self.fields.iter().zip(&self.columns) {
+                column[row].write_packed(&mut cursor, endian, *field)?;
             }
+            writer.write_all(&entry)?;
         }
         let stringoff = self.header.stringoffset();
-        let mut end = writer.seek(SeekFrom::End(0))?;
+        let end = writer.seek(SeekFrom::End(0))?;
         if end != stringoff {
            let ioerr = std::io::Error::new(
             std::io::ErrorKind::UnexpectedEof, "End and StrOff don't match");
            return Err(ioerr.into())
         }
-        for value in &self.values {
-            if let Value::STRINGOFF((off, str)) = value {
-                let curoff = writer.seek(SeekFrom::Current(0))?;
-                let realoff = *off as i64;
-                writer.seek(SeekFrom::Current(realoff))?;
-                let (data, _, _) = SHIFT_JIS.encode(str);
-                writer.write_all(&data)?;
-                writer.write_ne(&0u8)?;
-                writer.seek(SeekFrom::Start(curoff))?;
-            }
-        }
-        end = writer.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    fn write_strings<W: Write + Seek>(writer: &mut W, strings: &[u8], pad_byte: u8) -> BinResult<()> {
+        writer.write_all(strings)?;
+        let end = writer.seek(SeekFrom::End(0))?;
         let padded = end + ((end + 31 & !31) - end);
         let dist = padded - end;
-        let buffer = vec![0x40u8; dist as usize];
+        let buffer = vec![pad_byte; dist as usize];
         writer.write_all(&buffer)?;
         Ok(())
     }
 
+    /// Size in bytes of what `write` produces: the entries, the string table and the
+    /// padding to 32 bytes.
+    pub fn len_bytes(&self) -> u64 {
+        let end = self.header.stringoffset() + self.string_table().byte_len() as u64;
+        (end + 31) & !31
+    }
+
+    /// Re-encodes a whole file from one endian to the other, e.g. Wii to PC.
+    pub fn swap_endian_file(input: &[u8], from: Endian, to: Endian) -> BinResult<Vec<u8>> {
+        Self::from_bytes(input, from)?.to_bytes(to)
+    }
+
     pub fn to_bytes(&self, endian: Endian) -> BinResult<Vec<u8>> {
         let mut stream = Cursor::new(vec![]);
         self.write(&mut stream, endian)?;
         Ok(stream.into_inner())
     }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct JsonField {
+    name: String,
+    #[serde(rename = "type")]
+    datatype: FieldType,
+    #[serde(default)]
+    mask: Option<u32>,
+    #[serde(default)]
+    shift: u8
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct JsonDocument {
+    fields: Vec<JsonField>,
+    #[serde(default)]
+    rows: Vec<serde_json::Map<String, serde_json::Value>>
+}
+
+#[cfg(feature = "serde")]
+impl BCSV {
+    /// A JSON document with a `fields` array of `name`/`type`/`mask`/`shift` objects and a
+    /// `rows` array of records keyed by field name. Unnamed fields use their `0x` hash.
+    pub fn to_json(&self, hashes: &HashMap<u32, String>) -> Result<String, BCSVError> {
+        use serde_json::{json, Map, Value as Json};
+        let names: Vec<String> = self.fields.iter().map(|x| x.get_name(hashes)).collect();
+        let fields: Vec<Json> = self.fields.iter().zip(&names).map(|(field, name)| json!({
+            "name": name, "type": field.get_field_type(), "mask": field.mask, "shift": field.shift
+        })).collect();
+        let rows: Vec<Json> = (0..self.complete_rows()).map(|row| {
+            let record: Map<String, Json> = names.iter().zip(&self.columns)
+                .map(|(name, column)| (name.clone(), column[row].to_json())).collect();
+            Json::Object(record)
+        }).collect();
+        Ok(serde_json::to_string_pretty(&json!({"fields": fields, "rows": rows}))?)
+    }
+
+    /// Parses a document written by `to_json`, recomputing the offsets and header like the
+    /// CSV path does. `mask` defaults to the type's mask and `shift` to 0.
+    pub fn from_json(text: &str) -> Result<BCSV, BCSVError> {
+        let doc: JsonDocument = serde_json::from_str(text)?;
+        let mut result = Self::new();
+        for field in &doc.fields {
+            result.fields.push(Field { hash: name_hash(&field.name)?, mask: field.mask.unwrap_or(field.datatype.mask()),
+                dataoff: 0, shift: field.shift, datatype: field.datatype as u8 });
+        }
+        result.columns = vec![Vec::with_capacity(doc.rows.len()); doc.fields.len()];
+        for row in &doc.rows {
+            for ((field, json), column) in result.fields.iter().zip(&doc.fields).zip(&mut result.columns) {
+                let cell = row.get(&json.name)
+                    .ok_or_else(|| format!("Row is missing field \"{}\"", json.name))?;
+                column.push(Value::from_json(*field, cell)?);
+            }
+        }
+        result.header.entrycount = doc.rows.len() as u32;
+        result.recompute_layout()?;
+        Ok(result)
+    }
+}
+
+/// Tables are equal when their headers, fields and columns match. The recorded `endian`
+/// and the defaults are ignored.
+impl PartialEq for BCSV {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header && self.fields == other.fields && self.columns == other.columns
+    }
+}
+
+/// Converts to CSV with `,` as the delimiter, SHORT and CHAR read as signed, and every
+/// header named by its `0x` hash. Use `convert_to_csv` to pass a hash list.
+impl TryFrom<&BCSV> for String {
+    type Error = BCSVError;
+
+    fn try_from(bcsv: &BCSV) -> Result<Self, Self::Error> {
+        Ok(bcsv.convert_to_csv(&HashMap::new(), true, ','))
+    }
+}
+
+/// Parses `,`-delimited CSV text, as written by `TryFrom<&BCSV> for String`.
+impl TryFrom<&str> for BCSV {
+    type Error = BCSVError;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        Ok(csv_parse::CSV::from_reader(text.as_bytes(), ',')?.create_bcsv())
+    }
+}
+
+/// Appends each value to the column of the field with the same hash; cells for fields that
+/// aren't in the table are ignored. Push whole rows so the columns stay balanced, then call
+/// `update_entry_count` to bring `entrycount` up to date.
+impl Extend<(Field, Value)> for BCSV {
+    fn extend<T: IntoIterator<Item = (Field, Value)>>(&mut self, iter: T) {
+        self.columns.resize_with(self.fields.len(), Vec::new);
+        for (field, value) in iter {
+            if let Some(i) = self.field_index(field) {
+                self.columns[i].push(value);
+            }
+        }
+    }
 }
\ No newline at end of file
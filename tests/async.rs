@@ -0,0 +1,27 @@
+#![cfg(feature = "async")]
+use std::{future::Future, task::{Context, Poll, Waker}};
+use libbcsv::{types::BCSV, Endian};
+
+const SAMPLE_BE: &[u8] = include_bytes!("fixtures/sample_be.bcsv");
+
+/// Polls `future` until it's done. Reading from a slice never waits, so a no-op waker is
+/// enough and no runtime is needed.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn read_async_matches_from_bytes() {
+    let mut bcsv = BCSV::new();
+    let mut reader = SAMPLE_BE;
+    block_on(bcsv.read_async(&mut reader, Endian::Big)).unwrap();
+    assert_eq!(bcsv, BCSV::from_bytes(SAMPLE_BE, Endian::Big).unwrap());
+    let mut truncated = &SAMPLE_BE[..20];
+    assert!(block_on(BCSV::new().read_async(&mut truncated, Endian::Big)).is_err());
+}
@@ -0,0 +1,732 @@
+use std::{collections::HashMap, io::Cursor};
+use libbcsv::{hash, types::*, Endian};
+
+const SAMPLE_BE: &[u8] = include_bytes!("fixtures/sample_be.bcsv");
+const SAMPLE_LE: &[u8] = include_bytes!("fixtures/sample_le.bcsv");
+const SAMPLE_CSV: &str = "Id:0,ObjName:6,Scale:2,Count:4,Flag:5\n\
+    0,Kuribo,1,3,0\n\
+    1,Kinopio,0.5,-1,-1\n\
+    -1,Kuribo,2.25,0,1\n";
+
+fn read(data: &[u8], endian: Endian) -> BCSV {
+    BCSV::from_bytes(data, endian).unwrap()
+}
+
+fn names() -> HashMap<u32, String> {
+    ["Id", "ObjName", "Scale", "Count", "Flag"].iter()
+        .map(|x| (hash::calchash(x), x.to_string())).collect()
+}
+
+#[test]
+fn reads_header_and_fields() {
+    for (data, endian) in [(SAMPLE_BE, Endian::Big), (SAMPLE_LE, Endian::Little)] {
+        let bcsv = read(data, endian);
+        assert_eq!(bcsv.header.entrycount, 3);
+        assert_eq!(bcsv.header.fieldcount, 5);
+        assert_eq!(bcsv.header.entrydataoff, 76);
+        assert_eq!(bcsv.header.entrysize, 15);
+        let fields = bcsv.fields.iter()
+            .map(|x| (x.hash, x.get_field_type(), x.dataoff, x.mask)).collect::<Vec<_>>();
+        assert_eq!(fields, vec![
+            (hash::calchash("Id"), FieldType::LONG, 4, u32::MAX),
+            (hash::calchash("ObjName"), FieldType::STRINGOFF, 8, u32::MAX),
+            (hash::calchash("Scale"), FieldType::FLOAT, 0, 0),
+            (hash::calchash("Count"), FieldType::SHORT, 12, 0xFFFF),
+            (hash::calchash("Flag"), FieldType::CHAR, 14, 0xFF)
+        ]);
+    }
+}
+
+#[test]
+fn converts_to_csv() {
+    for (data, endian) in [(SAMPLE_BE, Endian::Big), (SAMPLE_LE, Endian::Little)] {
+        let bcsv = read(data, endian);
+        assert_eq!(bcsv.convert_to_csv(&names(), true, ','), SAMPLE_CSV);
+    }
+}
+
+#[test]
+fn round_trips_to_identical_bytes() {
+    for (data, endian) in [(SAMPLE_BE, Endian::Big), (SAMPLE_LE, Endian::Little)] {
+        let bcsv = read(data, endian);
+        assert_eq!(bcsv.to_bytes(endian).unwrap(), data);
+    }
+}
+
+#[test]
+fn converts_between_endians() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    assert_eq!(bcsv.to_bytes(Endian::Little).unwrap(), SAMPLE_LE);
+}
+
+#[test]
+fn swap_rows_keeps_columns_aligned() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    bcsv.swap_rows(0, 2).unwrap();
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), "Id:0,ObjName:6,Scale:2,Count:4,Flag:5\n\
+        -1,Kuribo,2.25,0,1\n\
+        1,Kinopio,0.5,-1,-1\n\
+        0,Kuribo,1,3,0\n");
+    assert!(bcsv.swap_rows(0, 3).is_err());
+}
+
+#[test]
+fn remove_row_keeps_columns_aligned() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    bcsv.remove_row(0).unwrap();
+    assert_eq!(bcsv.header.entrycount, 2);
+    assert!(bcsv.remove_row(2).is_err());
+    let expected = "Id:0,ObjName:6,Scale:2,Count:4,Flag:5\n\
+        1,Kinopio,0.5,-1,-1\n\
+        -1,Kuribo,2.25,0,1\n";
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), expected);
+    let reread = read(&bcsv.to_bytes(Endian::Big).unwrap(), Endian::Big);
+    assert_eq!(reread.convert_to_csv(&names(), true, ','), expected);
+}
+
+#[test]
+fn sample_round_trips() {
+    let sample = BCSV::sample();
+    assert_eq!(sample.header.entrycount, 2);
+    assert_eq!(sample.header.fieldcount, 7);
+    assert!(sample.verify_offsets());
+    let bytes = sample.to_bytes(Endian::Big).unwrap();
+    let reread = read(&bytes, Endian::Big);
+    let hashes = HashMap::new();
+    assert_eq!(reread.convert_to_csv(&hashes, true, ','), sample.convert_to_csv(&hashes, true, ','));
+    assert_eq!(reread.to_bytes(Endian::Big).unwrap(), bytes);
+}
+
+#[test]
+fn write_same_endian_uses_read_endian() {
+    let bcsv = read(SAMPLE_LE, Endian::Little);
+    assert_eq!(bcsv.endian, Some(Endian::Little));
+    let mut out = Cursor::new(vec![]);
+    bcsv.write_same_endian(&mut out).unwrap();
+    assert_eq!(out.into_inner(), SAMPLE_LE);
+    assert!(BCSV::new().write_same_endian(&mut Cursor::new(vec![])).is_err());
+}
+
+#[test]
+fn write_verified_accepts_faithful_round_trip() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let mut out = Cursor::new(vec![]);
+    bcsv.write_verified(&mut out, Endian::Big).unwrap();
+    assert_eq!(out.into_inner(), SAMPLE_BE);
+    assert_eq!(read(SAMPLE_LE, Endian::Little), bcsv);
+}
+
+#[test]
+fn len_bytes_matches_written_size() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    assert_eq!(bcsv.len_bytes(), SAMPLE_BE.len() as u64);
+    let sample = BCSV::sample();
+    assert_eq!(sample.len_bytes(), sample.to_bytes(Endian::Big).unwrap().len() as u64);
+}
+
+#[test]
+fn validate_names_first_inconsistency() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    assert!(bcsv.validate().is_ok());
+    assert!(BCSV::new().validate().is_ok());
+    let mut bad = bcsv.clone();
+    bad.header.entrysize += 4;
+    assert!(bad.validate().unwrap_err().to_string().contains("entrysize"));
+    let mut bad = bcsv.clone();
+    bad.header.entrycount += 1;
+    assert!(bad.validate().unwrap_err().to_string().contains("entrycount"));
+    let mut bad = bcsv;
+    let hash = bad.fields[0].hash;
+    bad.fields[1].hash = hash;
+    assert_eq!(bad.validate().unwrap_err().to_string(), format!("Field 0x{:X} appears more than once", hash));
+}
+
+#[test]
+fn write_rejects_unbalanced_columns() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    bcsv.get_by_name_mut("Id").unwrap().push(Value::LONG(2));
+    let err = bcsv.to_bytes(Endian::Big).unwrap_err();
+    assert!(err.to_string().contains("has 4 values but entrycount is 3"));
+    let mut out = std::io::Cursor::new(vec![]);
+    assert!(bcsv.write(&mut out, Endian::Big).is_err());
+}
+
+#[test]
+fn read_auto_detects_endian() {
+    for (data, endian) in [(SAMPLE_BE, Endian::Big), (SAMPLE_LE, Endian::Little)] {
+        let (bcsv, detected) = BCSV::read_auto(&mut Cursor::new(data)).unwrap();
+        assert_eq!(detected, endian);
+        assert_eq!(bcsv, read(data, endian));
+    }
+    assert!(BCSV::read_auto(&mut Cursor::new([0xFFu8; 32])).is_err());
+    // Padding between the field table and the entries is accepted, as it is by `read`.
+    let mut data = SAMPLE_BE.to_vec();
+    data.splice(76..76, [0; 4]);
+    data[8..12].copy_from_slice(&80u32.to_be_bytes());
+    let (bcsv, detected) = BCSV::read_auto(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(detected, Endian::Big);
+    assert_eq!(bcsv, read(&data, Endian::Big));
+}
+
+#[test]
+fn resolves_unknown_field_names() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let mut hashes = names();
+    hashes.remove(&hash::calchash("Scale"));
+    hashes.remove(&hash::calchash("Flag"));
+    assert_eq!(bcsv.unknown_hashes(&hashes), [hash::calchash("Scale"), hash::calchash("Flag")]);
+    bcsv.resolve_names(&mut hashes, &["Nothing", "Flag", "Id"]);
+    assert_eq!(bcsv.unknown_hashes(&hashes), [hash::calchash("Scale")]);
+    assert_eq!(hashes.get(&hash::calchash("Flag")).map(String::as_str), Some("Flag"));
+    assert!(!hashes.contains_key(&hash::calchash("Nothing")));
+}
+
+#[test]
+fn convert_to_csv_keeps_columns_aligned() {
+    let records = [
+        HashMap::from([("A".to_string(), Value::LONG(1)), ("B".to_string(), Value::FLOAT(0.5)),
+            ("C".to_string(), Value::STRINGOFF((0, "x".into())))]),
+        HashMap::from([("A".to_string(), Value::LONG(-2)), ("B".to_string(), Value::FLOAT(1.5)),
+            ("C".to_string(), Value::STRINGOFF((0, "y".into())))])
+    ];
+    let bcsv = BCSV::from_records(&records).unwrap();
+    let hashes = ["A", "B", "C"].iter().map(|x| (hash::calchash(x), x.to_string())).collect();
+    assert_eq!(bcsv.convert_to_csv(&hashes, true, ','), "A:0,B:2,C:6\n1,0.5,x\n-2,1.5,y\n");
+}
+
+#[test]
+fn to_csv_writer_streams_convert_to_csv_text() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let mut out = vec![];
+    bcsv.to_csv_writer(&mut out, &names(), true, ',').unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), SAMPLE_CSV);
+    let mut out = vec![];
+    bcsv.to_csv_writer(&mut out, &HashMap::new(), false, '\t').unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), bcsv.convert_to_csv(&HashMap::new(), false, '\t'));
+}
+
+#[test]
+fn writes_with_custom_pad_byte() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let mut stream = Cursor::new(vec![]);
+    bcsv.write_with_options(&mut stream, Endian::Big, 0).unwrap();
+    let data = stream.into_inner();
+    assert_eq!(data.len(), SAMPLE_BE.len());
+    let pad = SAMPLE_BE.iter().rposition(|x| *x != 0x40).unwrap() + 1;
+    assert_eq!(data[..pad], SAMPLE_BE[..pad]);
+    assert!(data[pad..].iter().all(|x| *x == 0));
+    assert_eq!(read(&data, Endian::Big), bcsv);
+}
+
+#[test]
+fn unterminated_last_string_stops_at_padding() {
+    let mut data = SAMPLE_BE.to_vec();
+    // The terminator of "Kinopio", the last string before the 0x40 padding.
+    assert_eq!(data[135], 0);
+    data[135] = 0x40;
+    let bcsv = read(&data, Endian::Big);
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), SAMPLE_CSV);
+}
+
+#[test]
+fn rename_by_hash_rekeys_field() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    bcsv.rename_by_hash(hash::calchash("Flag"), "Enabled").unwrap();
+    let mut hashes = names();
+    hashes.insert(hash::calchash("Enabled"), "Enabled".into());
+    let csv = bcsv.convert_to_csv(&hashes, true, ',');
+    assert_eq!(csv, SAMPLE_CSV.replace("Flag:5", "Enabled:5"));
+    assert!(bcsv.rename_by_hash(hash::calchash("Flag"), "Other").is_err());
+    assert!(bcsv.rename_by_hash(hash::calchash("Enabled"), "Id").is_err());
+}
+
+#[test]
+fn columns_by_name_resolves_names() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let mut hashes = names();
+    hashes.remove(&hash::calchash("Flag"));
+    let columns = bcsv.columns_by_name(&hashes);
+    assert_eq!(columns.len(), 5);
+    let scale = columns["Scale"].iter().map(|x| x.get_string(true)).collect::<Vec<_>>();
+    assert_eq!(scale, ["1", "0.5", "2.25"]);
+    assert!(columns.contains_key(&format!("0x{:X}", hash::calchash("Flag"))));
+}
+
+#[test]
+fn dedup_rows_keeps_first_of_each() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    let fields = bcsv.fields.clone();
+    let first = [Value::LONG(0), Value::STRINGOFF((0, "Kuribo".into())), Value::FLOAT(1.0),
+        Value::SHORT(3), Value::CHAR(0)];
+    let second = [Value::LONG(1), Value::STRINGOFF((0, "Kinopio".into())), Value::FLOAT(0.5),
+        Value::SHORT(0xFFFF), Value::CHAR(0xFF)];
+    bcsv.extend(fields.iter().copied().zip(first));
+    bcsv.extend(fields.iter().copied().zip(second));
+    bcsv.update_entry_count();
+    // Rows are now first, second, third, first, second; move a duplicate between the originals.
+    bcsv.swap_rows(2, 3).unwrap();
+    assert_eq!(bcsv.dedup_rows(), 2);
+    assert_eq!(bcsv.header.entrycount, 3);
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), SAMPLE_CSV);
+    assert_eq!(bcsv.to_bytes(Endian::Big).unwrap(), SAMPLE_BE);
+}
+
+#[test]
+fn read_and_write_fields_alone() {
+    let mut reader = Cursor::new(&SAMPLE_BE[16..]);
+    let fields = BCSV::read_fields(&mut reader, Endian::Big, 5).unwrap();
+    assert_eq!(fields, read(SAMPLE_BE, Endian::Big).fields);
+    let mut out = Cursor::new(vec![]);
+    BCSV::write_fields(&mut out, &fields, Endian::Big).unwrap();
+    assert_eq!(out.into_inner(), &SAMPLE_BE[16..76]);
+}
+
+#[test]
+fn pretty_print_aligns_columns() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    assert_eq!(bcsv.pretty_print(&names(), true, Some(2)), "\
+        Id:LONG | ObjName:STRINGOFF | Scale:FLOAT | Count:SHORT | Flag:CHAR\n\
+        --------+-------------------+-------------+-------------+----------\n\
+        0       | Kuribo            | 1           | 3           | 0\n\
+        1       | Kinopio           | 0.5         | -1          | -1\n\
+        ... 1 more rows\n");
+    // Only complete rows count, so an unbalanced table doesn't overstate what's hidden.
+    let mut unbalanced = bcsv;
+    unbalanced.header.entrycount = 5;
+    assert!(unbalanced.pretty_print(&names(), true, Some(2)).ends_with("... 1 more rows\n"));
+}
+
+#[test]
+fn add_row_uses_configured_defaults() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    bcsv.defaults.insert(hash::calchash("Count"), Value::SHORT(0xFFFF));
+    bcsv.defaults.insert(hash::calchash("ObjName"), Value::STRINGOFF((0, "Kuribo".into())));
+    // Wrong type, so Flag falls back to zero.
+    bcsv.defaults.insert(hash::calchash("Flag"), Value::LONG(5));
+    assert_eq!(bcsv.add_row(), 3);
+    let csv = bcsv.convert_to_csv(&names(), true, ',');
+    assert_eq!(csv.lines().last(), Some("0,Kuribo,0,-1,0"));
+    let bytes = bcsv.to_bytes(Endian::Big).unwrap();
+    assert_eq!(read(&bytes, Endian::Big).convert_to_csv(&names(), true, ','), csv);
+}
+
+#[test]
+fn try_from_round_trips_through_csv_text() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let csv: String = (&bcsv).try_into().unwrap();
+    assert!(csv.starts_with(&format!("0x{:X}:0,", hash::calchash("Id"))));
+    let parsed = BCSV::try_from(csv.as_str()).unwrap();
+    assert_eq!(parsed.to_bytes(Endian::Big).unwrap(), SAMPLE_BE);
+}
+
+#[test]
+fn read_rows_samples_given_indices() {
+    let bcsv = BCSV::read_rows(&mut Cursor::new(SAMPLE_BE), Endian::Big, &[2, 1]).unwrap();
+    assert_eq!(bcsv.header.entrycount, 2);
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), "Id:0,ObjName:6,Scale:2,Count:4,Flag:5\n\
+        -1,Kuribo,2.25,0,1\n\
+        1,Kinopio,0.5,-1,-1\n");
+    let bytes = bcsv.to_bytes(Endian::Big).unwrap();
+    assert_eq!(read(&bytes, Endian::Big).convert_to_csv(&names(), true, ','),
+        bcsv.convert_to_csv(&names(), true, ','));
+    assert!(BCSV::read_rows(&mut Cursor::new(SAMPLE_BE), Endian::Big, &[3]).is_err());
+}
+
+#[test]
+fn write_rejects_strings_outside_shift_jis() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    let field = bcsv.fields[1];
+    bcsv.extend([(field, Value::STRINGOFF((0, "Kinopio\u{1F600}".into())))]);
+    for other in bcsv.fields.clone().into_iter().filter(|x| *x != field) {
+        bcsv.extend([(other, Value::new(other))]);
+    }
+    bcsv.update_entry_count();
+    let err = bcsv.to_bytes(Endian::Big).unwrap_err();
+    assert!(err.to_string().contains("SHIFT_JIS"));
+}
+
+#[test]
+fn map_field_hashes_rehashes_every_field() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    let old = names().into_iter().map(|(hash, name)| (hash, hash::calc_old_hash(&name))).collect::<HashMap<_, _>>();
+    bcsv.map_field_hashes(|x| old[&x]).unwrap();
+    let renamed = names().into_values().map(|x| (hash::calc_old_hash(&x), x)).collect();
+    assert_eq!(bcsv.convert_to_csv(&renamed, true, ','), SAMPLE_CSV);
+    assert!(bcsv.map_field_hashes(|_| 0).is_err());
+    assert_eq!(bcsv.convert_to_csv(&renamed, true, ','), SAMPLE_CSV);
+}
+
+#[test]
+fn csv_lines_matches_convert_to_csv() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let hashes = names();
+    let lines = bcsv.csv_lines(&hashes, true, ',').collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[2], "1,Kinopio,0.5,-1,-1");
+    assert_eq!(lines.join("\n") + "\n", SAMPLE_CSV);
+}
+
+#[test]
+fn zero_field_table_round_trips() {
+    let mut data = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 0];
+    data.extend([0x40; 16]);
+    let bcsv = read(&data, Endian::Big);
+    assert!(bcsv.fields.is_empty());
+    assert_eq!(bcsv.header.entrydataoff, 16);
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), "");
+    assert_eq!(bcsv.len_bytes(), 32);
+    assert_eq!(bcsv.to_bytes(Endian::Big).unwrap(), data);
+    assert_eq!(BCSV::new().to_bytes(Endian::Big).unwrap(), data);
+}
+
+#[test]
+fn zero_field_table_has_no_csv_lines() {
+    let mut data = vec![0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 0];
+    data.extend([0x40; 16]);
+    let bcsv = read(&data, Endian::Big);
+    assert_eq!(bcsv.header.entrycount, 3);
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), "");
+    assert_eq!(bcsv.csv_lines(&names(), true, ',').count(), 0);
+}
+
+#[test]
+fn round_trip_keeps_original_field_layout() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    for (field, dataoff) in bcsv.fields.iter_mut().zip([11, 4, 0, 8, 10]) {
+        field.dataoff = dataoff;
+    }
+    assert!(bcsv.verify_offsets());
+    let bytes = bcsv.to_bytes(Endian::Big).unwrap();
+    let reread = read(&bytes, Endian::Big);
+    assert_eq!(reread.fields, bcsv.fields);
+    assert_eq!(reread.convert_to_csv(&names(), true, ','), SAMPLE_CSV);
+    assert_eq!(reread.to_bytes(Endian::Big).unwrap(), bytes);
+}
+
+#[test]
+fn validate_against_file_len_checks_extents() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    assert!(bcsv.validate_against_file_len(SAMPLE_BE.len() as u64).is_ok());
+    // "Kinopio" and its terminator end at 136.
+    assert!(bcsv.validate_against_file_len(136).is_ok());
+    assert!(bcsv.validate_against_file_len(135).is_err());
+    assert!(bcsv.validate_against_file_len(100).is_err());
+}
+
+#[test]
+fn write_to_path_replaces_existing_file() {
+    let path = std::env::temp_dir().join(format!("libbcsv_path_{}.bcsv", std::process::id()));
+    std::fs::write(&path, vec![0xAA; 512]).unwrap();
+    read(SAMPLE_BE, Endian::Big).write_to_path(&path, Endian::Little).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), SAMPLE_LE);
+    let bcsv = BCSV::read_from_path(&path, Endian::Little).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(bcsv.convert_to_csv(&names(), true, ','), SAMPLE_CSV);
+}
+
+#[test]
+fn count_distinct_per_column() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let field = |name| Field::from_hash(hash::calchash(name), FieldType::NULL);
+    assert_eq!(bcsv.count_distinct(field("ObjName")), Some(2));
+    assert_eq!(bcsv.count_distinct(field("Scale")), Some(3));
+    assert_eq!(bcsv.count_distinct(field("Missing")), None);
+}
+
+#[test]
+fn get_by_name_accepts_names_and_hashes() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    let ids = bcsv.get_by_name("Id").unwrap().iter().map(|x| x.get_string(true)).collect::<Vec<_>>();
+    assert_eq!(ids, ["0", "1", "-1"]);
+    let raw = format!("0x{:X}", hash::calchash("Id"));
+    assert_eq!(bcsv.get_by_name(&raw), bcsv.get_by_name("Id"));
+    assert!(bcsv.get_by_name("Missing").is_none());
+    assert!(bcsv.get_by_name("0xZZ").is_none());
+    bcsv.get_by_name_mut("Id").unwrap()[0] = Value::LONG(7);
+    assert!(bcsv.convert_to_csv(&names(), true, ',').contains("\n7,Kuribo,"));
+}
+
+#[test]
+fn rows_follow_field_order() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let text = |row: Vec<&Value>| row.iter().map(|x| x.get_string(true)).collect::<Vec<_>>().join(",");
+    let rows = bcsv.rows().map(text).collect::<Vec<_>>();
+    assert_eq!(rows, SAMPLE_CSV.lines().skip(1).collect::<Vec<_>>());
+    assert_eq!(bcsv.row(1).map(text).as_deref(), Some("1,Kinopio,0.5,-1,-1"));
+    assert!(bcsv.row(3).is_none());
+}
+
+#[test]
+fn schema_bytes_round_trip() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let schema = bcsv.export_schema_bytes();
+    assert_eq!(schema, &SAMPLE_BE[..76]);
+    let mut empty = BCSV::import_schema_bytes(&schema).unwrap();
+    assert_eq!(empty.fields, bcsv.fields);
+    assert_eq!(empty.header.entrycount, 0);
+    assert_eq!(empty.header.entrysize, bcsv.header.entrysize);
+    empty.add_row();
+    assert_eq!(empty.to_bytes(Endian::Big).unwrap().len(), 96);
+    assert!(BCSV::import_schema_bytes(&schema[..70]).is_err());
+}
+
+#[test]
+fn value_histogram_counts_integers() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let field = |name| Field::from_hash(hash::calchash(name), FieldType::NULL);
+    let ids = bcsv.value_histogram(field("Id")).unwrap();
+    assert_eq!(ids, HashMap::from([(0, 1), (1, 1), (-1, 1)]));
+    let flags = bcsv.value_histogram(field("Flag")).unwrap();
+    assert_eq!(flags, HashMap::from([(0, 1), (0xFF, 1), (1, 1)]));
+    assert!(bcsv.value_histogram(field("Scale")).is_none());
+    assert!(bcsv.value_histogram(field("Missing")).is_none());
+    assert_eq!(bcsv.field_index(field("Count")), Some(3));
+    assert_eq!(bcsv.field_index(field("Missing")), None);
+}
+
+#[test]
+fn remove_field_fixes_up_layout() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    let removed = bcsv.remove_field(Field::from_hash(hash::calchash("ObjName"), FieldType::NULL)).unwrap().unwrap();
+    assert_eq!(removed.len(), 3);
+    assert_eq!(bcsv.header.fieldcount, 4);
+    assert_eq!(bcsv.header.entrydataoff, 64);
+    assert_eq!(bcsv.header.entrysize, 11);
+    assert!(bcsv.verify_offsets());
+    let reread = read(&bcsv.to_bytes(Endian::Big).unwrap(), Endian::Big);
+    assert_eq!(reread.convert_to_csv(&names(), true, ','), "Id:0,Scale:2,Count:4,Flag:5\n\
+        0,1,3,0\n\
+        1,0.5,-1,-1\n\
+        -1,2.25,0,1\n");
+    assert!(bcsv.remove_field(Field::from_hash(hash::calchash("ObjName"), FieldType::NULL)).unwrap().is_none());
+}
+
+#[test]
+fn insert_row_validates_shape() {
+    let mut bcsv = read(SAMPLE_BE, Endian::Big);
+    let row = vec![Value::LONG(5), Value::STRINGOFF((0, "Kinopio".into())), Value::FLOAT(3.0),
+        Value::SHORT(2), Value::CHAR(1)];
+    assert!(bcsv.insert_row(0, row[..4].to_vec()).is_err());
+    let mut wrong = row.clone();
+    wrong[0] = Value::ULONG(5);
+    assert!(bcsv.insert_row(0, wrong).is_err());
+    assert!(bcsv.insert_row(4, row.clone()).is_err());
+    bcsv.insert_row(1, row).unwrap();
+    assert_eq!(bcsv.header.entrycount, 4);
+    let reread = read(&bcsv.to_bytes(Endian::Big).unwrap(), Endian::Big);
+    let lines = reread.csv_lines(&HashMap::new(), true, ',').skip(1).collect::<Vec<_>>();
+    assert_eq!(lines, ["0,Kuribo,1,3,0", "5,Kinopio,3,2,1", "1,Kinopio,0.5,-1,-1", "-1,Kuribo,2.25,0,1"]);
+}
+
+#[test]
+fn swap_endian_file_round_trips() {
+    let little = BCSV::swap_endian_file(SAMPLE_BE, Endian::Big, Endian::Little).unwrap();
+    assert_eq!(little, SAMPLE_LE);
+    let big = BCSV::swap_endian_file(&little, Endian::Little, Endian::Big).unwrap();
+    assert_eq!(big, SAMPLE_BE);
+}
+
+#[test]
+fn read_rejects_unknown_field_types() {
+    let mut data = SAMPLE_BE.to_vec();
+    // Datatype byte of the last field, Flag.
+    assert_eq!(data[16 + 12 * 4 + 11], FieldType::CHAR as u8);
+    data[16 + 12 * 4 + 11] = 9;
+    let err = BCSV::from_bytes(&data, Endian::Big).unwrap_err();
+    assert!(err.to_string().contains(&format!("0x{:X} has unknown datatype 9", hash::calchash("Flag"))));
+    assert!(BCSV::read_rows(&mut Cursor::new(&data), Endian::Big, &[0]).is_err());
+}
+
+#[test]
+fn read_rejects_shift_past_field_width() {
+    let mut data = SAMPLE_BE.to_vec();
+    // Shift byte of the first field, Id, a LONG.
+    assert_eq!(data[16 + 11], FieldType::LONG as u8);
+    data[16 + 10] = 40;
+    let err = BCSV::from_bytes(&data, Endian::Big).unwrap_err();
+    assert!(err.to_string().contains(&format!("0x{:X} has shift 40 past its 32-bit type", hash::calchash("Id"))));
+    assert!(BCSV::read_rows(&mut Cursor::new(&data), Endian::Big, &[0]).is_err());
+    data[16 + 10] = 31;
+    assert!(BCSV::from_bytes(&data, Endian::Big).is_ok());
+}
+
+#[test]
+fn rejects_duplicate_field_hashes() {
+    let mut data = SAMPLE_BE.to_vec();
+    // Give the second field (ObjName) the hash of the first (Id).
+    let (first, second) = (16, 16 + 12);
+    data.copy_within(first..first + 4, second);
+    let err = BCSV::from_bytes(&data, Endian::Big).unwrap_err();
+    assert!(err.to_string().contains(&format!("0x{:X} appears more than once", hash::calchash("Id"))));
+    assert!(BCSV::read_rows(&mut Cursor::new(&data), Endian::Big, &[0]).is_err());
+}
+
+#[test]
+fn rejects_header_that_overruns_file() {
+    let header = read(SAMPLE_BE, Endian::Big).header;
+    assert!(header.is_consistent(SAMPLE_BE.len() as u64));
+    assert!(!header.is_consistent(header.stringoffset() - 1));
+    assert!(!Header { entrycount: u32::MAX, entrysize: u32::MAX, ..header }.is_consistent(u64::MAX / 2));
+    let mut data = SAMPLE_BE.to_vec();
+    data[..4].copy_from_slice(&0x0FFF_FFFFu32.to_be_bytes());
+    let err = BCSV::from_bytes(&data, Endian::Big).unwrap_err();
+    assert!(err.to_string().contains("doesn't fit a file"));
+    assert!(BCSV::read_rows(&mut Cursor::new(&data), Endian::Big, &[0]).is_err());
+    // A field table running past entrydataoff is caught by the same check.
+    let mut data = SAMPLE_BE.to_vec();
+    data[4..8].copy_from_slice(&6u32.to_be_bytes());
+    let err = BCSV::from_bytes(&data, Endian::Big).unwrap_err();
+    assert!(err.to_string().contains("doesn't fit a file"));
+}
+
+#[test]
+fn read_fields_survives_huge_count() {
+    let mut reader = Cursor::new(&SAMPLE_BE[16..]);
+    assert!(BCSV::read_fields(&mut reader, Endian::Big, u32::MAX).is_err());
+    let mut data = SAMPLE_BE.to_vec();
+    data[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(BCSV::from_bytes(&data, Endian::Big).is_err());
+    assert!(BCSV::read_rows(&mut Cursor::new(&data), Endian::Big, &[0]).is_err());
+}
+
+#[test]
+fn packed_fields_sharing_dataoff_round_trip() {
+    let a = Field { hash: hash::calchash("A"), mask: 0xF, shift: 0, dataoff: 0, datatype: FieldType::SHORT as u8 };
+    let b = Field { hash: hash::calchash("B"), mask: 0xF0, shift: 4, dataoff: 0, datatype: FieldType::SHORT as u8 };
+    let mut bcsv = BCSV::new();
+    bcsv.fields = vec![a, b];
+    bcsv.header = Header { entrycount: 1, fieldcount: 2, entrydataoff: 16 + 12 * 2, entrysize: 2 };
+    bcsv.extend([(a, Value::SHORT(3)), (b, Value::SHORT(5))]);
+    let data = bcsv.to_bytes(Endian::Big).unwrap();
+    assert_eq!(data[40..42], [0x00, 0x53]);
+    let back = read(&data, Endian::Big);
+    assert_eq!(back.get_by_name("A"), Some(&vec![Value::SHORT(3)]));
+    assert_eq!(back.get_by_name("B"), Some(&vec![Value::SHORT(5)]));
+    assert_eq!(back, bcsv);
+}
+
+#[test]
+fn repair_offsets_keeps_packed_fields_together() {
+    let mut bcsv = BCSV::new();
+    bcsv.fields = vec![
+        Field { hash: hash::calchash("Id"), mask: u32::MAX, shift: 0, dataoff: 7, datatype: FieldType::LONG as u8 },
+        Field { hash: hash::calchash("A"), mask: 0xF, shift: 0, dataoff: 3, datatype: FieldType::SHORT as u8 },
+        Field { hash: hash::calchash("B"), mask: 0xF0, shift: 4, dataoff: 3, datatype: FieldType::SHORT as u8 },
+        Field { hash: hash::calchash("C"), mask: 0xFF, shift: 0, dataoff: 3, datatype: FieldType::SHORT as u8 },
+    ];
+    assert!(!bcsv.verify_offsets());
+    bcsv.repair_offsets();
+    let offsets: Vec<_> = bcsv.fields.iter().map(|x| x.dataoff).collect();
+    // C overlaps A and B, so it is moved to a slot of its own.
+    assert_eq!(offsets, [0, 4, 4, 6]);
+    assert_eq!(bcsv.header.entrysize, 8);
+    assert!(bcsv.verify_offsets());
+}
+
+#[test]
+fn verify_offsets_rejects_gaps_and_overlapping_masks() {
+    let mut bcsv = BCSV::new();
+    bcsv.fields = vec![
+        Field { hash: hash::calchash("A"), mask: 0xF, shift: 0, dataoff: 0, datatype: FieldType::SHORT as u8 },
+        Field { hash: hash::calchash("B"), mask: 0xF0, shift: 4, dataoff: 0, datatype: FieldType::SHORT as u8 },
+    ];
+    bcsv.header.entrysize = 2;
+    assert!(bcsv.verify_offsets());
+    bcsv.fields[1].mask = 0x18;
+    assert!(!bcsv.verify_offsets());
+    bcsv.fields[1].mask = 0xF0;
+    bcsv.fields[1].datatype = FieldType::CHAR as u8;
+    assert!(!bcsv.verify_offsets());
+    bcsv.fields[1].datatype = FieldType::SHORT as u8;
+    bcsv.fields[1].dataoff = 4;
+    bcsv.header.entrysize = 6;
+    assert!(!bcsv.verify_offsets());
+}
+
+#[test]
+fn header_setters_reject_overflowing_counts() {
+    let mut header = Header::default();
+    header.set_field_count(5).unwrap();
+    assert_eq!((header.fieldcount, header.entrydataoff), (5, 76));
+    assert!(header.set_field_count(u32::MAX).is_err());
+    assert!(header.set_field_count((u32::MAX - 16) / 12 + 1).is_err());
+    assert_eq!((header.fieldcount, header.entrydataoff), (5, 76));
+    header.set_fields(&read(SAMPLE_BE, Endian::Big).fields).unwrap();
+    assert_eq!((header.fieldcount, header.entrydataoff, header.entrysize), (5, 76, 15));
+    let packed = [
+        Field { hash: hash::calchash("A"), mask: 0xF, shift: 0, dataoff: 0, datatype: FieldType::SHORT as u8 },
+        Field { hash: hash::calchash("B"), mask: 0xF0, shift: 4, dataoff: 0, datatype: FieldType::SHORT as u8 },
+        Field { hash: hash::calchash("C"), mask: u32::MAX, shift: 0, dataoff: 2, datatype: FieldType::LONG as u8 },
+    ];
+    header.set_fields(&packed).unwrap();
+    assert_eq!((header.fieldcount, header.entrydataoff, header.entrysize), (3, 52, 6));
+}
+
+#[test]
+fn write_null_pads_string_after_terminator() {
+    let mut bcsv = BCSV::try_from("Name:1\nFoo\n").unwrap();
+    let mut raw = [0u8; 32];
+    raw[..4].copy_from_slice(b"Foo\0");
+    raw[4..].fill(b'x');
+    bcsv.get_by_name_mut("Name").unwrap()[0] = Value::STRING(raw);
+    let data = bcsv.to_bytes(Endian::Big).unwrap();
+    let start = bcsv.header.entrydataoff as usize;
+    assert_eq!(&data[start..start + 3], b"Foo");
+    assert!(data[start + 3..start + 32].iter().all(|x| *x == 0));
+}
+
+#[test]
+fn shared_dataoff_needs_packable_fields() {
+    let mut bcsv = BCSV::new();
+    bcsv.fields = vec![
+        Field { hash: hash::calchash("A"), mask: 0, shift: 0, dataoff: 0, datatype: FieldType::FLOAT as u8 },
+        Field { hash: hash::calchash("B"), mask: 0, shift: 0, dataoff: 0, datatype: FieldType::FLOAT as u8 },
+    ];
+    bcsv.header = Header { entrycount: 0, fieldcount: 2, entrydataoff: 40, entrysize: 4 };
+    assert!(!bcsv.verify_offsets());
+    assert!(bcsv.validate().unwrap_err().to_string().contains("can't be packed"));
+    bcsv.repair_offsets();
+    assert_eq!((bcsv.fields[1].dataoff, bcsv.header.entrysize), (4, 8));
+    assert!(bcsv.verify_offsets());
+}
+
+#[test]
+fn write_with_string_table_uses_its_offsets() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let mut table = libbcsv::string_table::StringTable::new();
+    table.push("Teresa").push("Kuribo").push("Kinopio");
+    let mut out = Cursor::new(vec![]);
+    bcsv.write_with_string_table(&mut out, Endian::Big, &table).unwrap();
+    let back = read(out.get_ref(), Endian::Big);
+    let objnames = back.get_by_name("ObjName").unwrap();
+    assert!(matches!(&objnames[0], Value::STRINGOFF((7, x)) if x == "Kuribo"));
+    assert!(matches!(&objnames[1], Value::STRINGOFF((14, x)) if x == "Kinopio"));
+    assert_eq!(back.convert_to_csv(&names(), true, ','), SAMPLE_CSV);
+    let mut missing = libbcsv::string_table::StringTable::new();
+    missing.push("Kuribo");
+    let err = bcsv.write_with_string_table(&mut Cursor::new(vec![]), Endian::Big, &missing).unwrap_err();
+    assert!(err.to_string().contains("\"Kinopio\" is missing from the string table"));
+}
+
+#[test]
+fn check_types_reports_mismatches() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let expected = HashMap::from([
+        (hash::calchash("Id"), FieldType::LONG),
+        (hash::calchash("Count"), FieldType::LONG),
+        (hash::calchash("Missing"), FieldType::FLOAT),
+    ]);
+    assert_eq!(bcsv.check_types(&expected), [(hash::calchash("Count"), FieldType::LONG, FieldType::SHORT)]);
+    assert!(bcsv.check_types(&HashMap::new()).is_empty());
+}
+
+#[test]
+fn cell_offset_points_into_the_file() {
+    let bcsv = read(SAMPLE_BE, Endian::Big);
+    let field = |name| Field::from_hash(hash::calchash(name), FieldType::NULL);
+    assert_eq!(bcsv.cell_offset(1, field("Count")), Some((76 + 15 + 12, 2)));
+    assert_eq!(SAMPLE_BE[103..105], [0xFF, 0xFF]);
+    assert_eq!(bcsv.cell_offset(0, field("Id")), Some((76 + 4, 4)));
+    assert_eq!(bcsv.cell_offset(3, field("Count")), None);
+    assert_eq!(bcsv.cell_offset(0, field("Missing")), None);
+}
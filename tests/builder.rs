@@ -0,0 +1,54 @@
+use libbcsv::{builder::BcsvBuilder, hash, types::*, Endian};
+
+const SAMPLE_BE: &[u8] = include_bytes!("fixtures/sample_be.bcsv");
+
+#[test]
+fn builds_sample_layout() {
+    let strings = |x: &str| Value::STRINGOFF((0, x.into()));
+    let bcsv = BcsvBuilder::new()
+        .add_field("Id", FieldType::LONG)
+        .add_field("ObjName", FieldType::STRINGOFF)
+        .add_field("Scale", FieldType::FLOAT)
+        .add_field("Count", FieldType::SHORT)
+        .add_field("Flag", FieldType::CHAR)
+        .push_row(vec![Value::LONG(0), strings("Kuribo"), Value::FLOAT(1.0), Value::SHORT(3), Value::CHAR(0)])
+        .push_row(vec![Value::LONG(1), strings("Kinopio"), Value::FLOAT(0.5), Value::SHORT(0xFFFF), Value::CHAR(0xFF)])
+        .push_row(vec![Value::LONG(-1), strings("Kuribo"), Value::FLOAT(2.25), Value::SHORT(0), Value::CHAR(1)])
+        .build().unwrap();
+    assert!(bcsv.validate().is_ok());
+    assert_eq!(bcsv.to_bytes(Endian::Big).unwrap(), SAMPLE_BE);
+}
+
+#[test]
+fn build_rejects_mismatched_rows() {
+    let builder = BcsvBuilder::new().add_field("Id", FieldType::LONG);
+    let err = builder.clone().push_row(vec![Value::FLOAT(1.0)]).build().unwrap_err();
+    assert!(err.to_string().contains("expected LONG"));
+    assert!(builder.clone().push_row(vec![]).build().is_err());
+    let dup = builder.add_field(format!("0x{:X}", hash::calchash("Id")), FieldType::CHAR).build();
+    assert!(dup.unwrap_err().to_string().contains("duplicate hash"));
+}
+
+#[test]
+fn build_keeps_float_and_string_fields_apart() {
+    let string = |x: &[u8]| {
+        let mut raw = [0u8; 32];
+        raw[..x.len()].copy_from_slice(x);
+        Value::STRING(raw)
+    };
+    let bcsv = BcsvBuilder::new()
+        .add_field("A", FieldType::FLOAT)
+        .add_field("B", FieldType::FLOAT)
+        .add_field("C", FieldType::STRING)
+        .add_field("D", FieldType::STRING)
+        .push_row(vec![Value::FLOAT(1.0), Value::FLOAT(2.0), string(b"foo"), string(b"bar")])
+        .build().unwrap();
+    assert_eq!(bcsv.header.entrysize, 72);
+    assert!(bcsv.verify_offsets());
+    assert!(bcsv.validate().is_ok());
+    let back = BCSV::from_bytes(bcsv.to_bytes(Endian::Big).unwrap(), Endian::Big).unwrap();
+    assert_eq!(back.get_by_name("A"), Some(&vec![Value::FLOAT(1.0)]));
+    assert_eq!(back.get_by_name("B"), Some(&vec![Value::FLOAT(2.0)]));
+    assert_eq!(back.get_by_name("C"), Some(&vec![string(b"foo")]));
+    assert_eq!(back.get_by_name("D"), Some(&vec![string(b"bar")]));
+}
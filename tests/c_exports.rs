@@ -0,0 +1,63 @@
+#![cfg(feature = "c_exports")]
+use std::ffi::{CStr, CString};
+use libbcsv::{c_exports::*, hash, types::Header};
+
+const SAMPLE_BE: &[u8] = include_bytes!("fixtures/sample_be.bcsv");
+
+fn last_error() -> String {
+    unsafe { CStr::from_ptr(bcsv_last_error()).to_string_lossy().into_owned() }
+}
+
+#[test]
+fn read_header_and_field_count() {
+    let mut header = Header::default();
+    unsafe {
+        assert_eq!(bcsv_read_header(SAMPLE_BE.as_ptr(), SAMPLE_BE.len(), 0, &mut header), 0);
+        assert_eq!(bcsv_field_count(SAMPLE_BE.as_ptr(), SAMPLE_BE.len(), 0), 5);
+        assert_eq!(bcsv_read_header(SAMPLE_BE.as_ptr(), 4, 0, &mut header), BCSV_ERR_READ);
+        assert_eq!(bcsv_field_count(std::ptr::null(), 0, 0), BCSV_ERR_NULL as i64);
+    }
+    assert_eq!((header.entrycount, header.fieldcount, header.entrydataoff, header.entrysize), (3, 5, 76, 15));
+    assert!(last_error().contains("null"));
+}
+
+#[test]
+fn handle_lists_fields() {
+    unsafe {
+        let handle = bcsv_open(SAMPLE_BE.as_ptr(), SAMPLE_BE.len(), 0, std::ptr::null());
+        assert!(!handle.is_null());
+        assert_eq!(bcsv_get_field_count(handle), 5);
+        let (mut hash, mut datatype, mut name) = (0u32, 0u8, [0i8; 4]);
+        let len = bcsv_get_field(handle, 0, &mut hash, &mut datatype, name.as_mut_ptr(), name.len());
+        assert_eq!((hash, datatype), (hash::calchash("Id"), 0));
+        assert_eq!(len, format!("0x{:X}", hash).len() as i64);
+        assert_eq!(CStr::from_ptr(name.as_ptr()).to_bytes(), &format!("0x{:X}", hash).as_bytes()[..3]);
+        assert_eq!(bcsv_get_field(handle, 5, &mut hash, &mut datatype, name.as_mut_ptr(), name.len()), BCSV_ERR_RANGE as i64);
+        bcsv_close(handle);
+        assert_eq!(bcsv_get_field_count(std::ptr::null()), BCSV_ERR_NULL as i64);
+        assert!(bcsv_open(SAMPLE_BE.as_ptr(), 8, 0, std::ptr::null()).is_null());
+    }
+}
+
+#[test]
+fn to_csv_reports_errors() {
+    unsafe {
+        let info = bcsv_to_csv(std::ptr::null(), SAMPLE_BE.as_ptr(), SAMPLE_BE.len(), 0, 0);
+        let text = std::slice::from_raw_parts(info.ptr, info.len);
+        assert!(text.starts_with(format!("0x{:X}:0,", hash::calchash("Id")).as_bytes()));
+        free_PtrInfo(info);
+        let missing = CString::new(std::env::temp_dir().join("libbcsv_no_such_hashes.txt")
+            .to_string_lossy().into_owned()).unwrap();
+        let info = bcsv_to_csv(missing.as_ptr(), SAMPLE_BE.as_ptr(), SAMPLE_BE.len(), 0, 0);
+        assert!(info.ptr.is_null());
+        assert!(!last_error().is_empty());
+        let info = bcsv_to_csv(std::ptr::null(), std::ptr::null(), 0, 0, 0);
+        assert!(info.ptr.is_null());
+        assert_eq!(last_error(), "data is null");
+        let info = bcsv_to_csv(std::ptr::null(), SAMPLE_BE.as_ptr(), SAMPLE_BE.len(), SAMPLE_BE.len() + 1, 0);
+        assert!(info.ptr.is_null());
+        assert!(last_error().contains("past the end"));
+        assert!(csv_to_bcsv(std::ptr::null(), 0).ptr.is_null());
+        assert_eq!(last_error(), "path is null");
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+use libbcsv::{convert, csv_parse::CSV, hash, types::{BCSV, Value}, Endian};
+
+const SAMPLE_BE: &[u8] = include_bytes!("fixtures/sample_be.bcsv");
+
+#[test]
+fn free_functions_round_trip_sample() {
+    let hashes: HashMap<u32, String> = ["Id", "ObjName", "Scale", "Count", "Flag"].iter()
+        .map(|x| (hash::calchash(x), x.to_string())).collect();
+    let bcsv = BCSV::from_bytes(SAMPLE_BE, Endian::Big).unwrap();
+    let text = convert::convert_to_csv(&bcsv, &hashes);
+    assert_eq!(text, bcsv.convert_to_csv(&hashes, true, ','));
+    let csv = CSV::from_reader(text.as_bytes(), ',').unwrap();
+    assert_eq!(convert::convert_to_bcsv(csv, Endian::Big, u32::MAX).unwrap(), SAMPLE_BE);
+}
+
+#[test]
+fn convert_to_bcsv_applies_mask_to_integer_fields() {
+    let text = "Id:0,Scale:2,Count:4
+4660,0.5,-1
+";
+    let csv = CSV::from_reader(text.as_bytes(), ',').unwrap();
+    let bcsv = BCSV::from_bytes(convert::convert_to_bcsv(csv, Endian::Big, 0xFF).unwrap(), Endian::Big).unwrap();
+    let mask = |name| bcsv.fields.iter().find(|x| x.hash == hash::calchash(name)).unwrap().mask;
+    assert_eq!((mask("Id"), mask("Scale"), mask("Count")), (0xFF, 0, 0xFF));
+    assert_eq!(bcsv.get_by_name("Id"), Some(&vec![Value::LONG(0x34)]));
+    assert_eq!(bcsv.get_by_name("Count"), Some(&vec![Value::SHORT(0xFF)]));
+    assert_eq!(bcsv.get_by_name("Scale"), Some(&vec![Value::FLOAT(0.5)]));
+}
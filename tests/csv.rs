@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use libbcsv::{csv_parse::CSV, hash, types::*, BCSVError};
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("libbcsv_{}_{}.csv", name, std::process::id()))
+}
+
+#[test]
+fn descriptor_escapes_colon_and_delimiter() {
+    let name = "Obj:Arg,100%";
+    let field = Field { hash: hash::calchash(name), datatype: 0, ..Default::default() };
+    let hashes = HashMap::from([(field.hash, String::from(name))]);
+    let desc = field.to_descriptor(&hashes, ',');
+    assert_eq!(desc, "Obj%3AArg%2C100%25:0");
+    let parsed = Field::from_descriptor(&desc).unwrap();
+    assert_eq!(parsed.hash, field.hash);
+    assert_eq!(parsed.get_field_type(), FieldType::LONG);
+}
+
+#[test]
+fn descriptor_accepts_type_names_and_mask() {
+    let short = Field::from_descriptor("Flags:SHORT").unwrap();
+    assert_eq!(short, Field::from_descriptor("Flags:4").unwrap());
+    assert_eq!(short.mask, 0xFFFF);
+    let packed = Field::from_descriptor("Flags:0xF0:4:SHORT").unwrap();
+    assert_eq!((packed.hash, packed.mask, packed.shift), (hash::calchash("Flags"), 0xF0, 4));
+    assert_eq!(Field::from_descriptor("Flags:240:4:4").unwrap(), packed);
+    assert!(Field::from_descriptor("Flags").is_err());
+    assert!(Field::from_descriptor("Flags:0xF0:SHORT").is_err());
+    assert!(Field::from_descriptor("Flags:WORD").is_err());
+}
+
+#[test]
+fn field_name_with_colon_survives_csv_round_trip() {
+    let name = "Obj:Arg";
+    let mut record = HashMap::new();
+    record.insert(String::from(name), Value::LONG(7));
+    let bcsv = BCSV::from_records(&[record]).unwrap();
+    let hashes = HashMap::from([(hash::calchash(name), String::from(name))]);
+    let text = bcsv.convert_to_csv(&hashes, false, ',');
+    let path = temp_path("colon");
+    std::fs::write(&path, &text).unwrap();
+    let imported = CSV::from_path(&path, ',').unwrap().create_bcsv();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(imported.fields[0].hash, hash::calchash(name));
+    assert_eq!(imported.convert_to_csv(&hashes, false, ','), text);
+}
+
+#[test]
+fn japanese_string_survives_round_trip() {
+    let text = "Name:1,Id:0\nクリボー,1\nKinopio,2\n";
+    let bytes = BCSV::try_from(text).unwrap().to_bytes(libbcsv::Endian::Big).unwrap();
+    // Stored as SHIFT_JIS, not UTF-8.
+    assert!(bytes.windows(8).any(|x| x == b"\x83\x4E\x83\x8A\x83\x7B\x81\x5B"));
+    let reread = BCSV::from_bytes(&bytes, libbcsv::Endian::Big).unwrap();
+    let hashes = ["Name", "Id"].iter().map(|x| (hash::calchash(x), x.to_string())).collect();
+    assert_eq!(reread.convert_to_csv(&hashes, true, ','), text);
+}
+
+#[test]
+fn errors_can_be_matched() {
+    let err = CSV::from_reader("Id:0\nabc\n".as_bytes(), ',').unwrap_err();
+    assert!(matches!(err, BCSVError::ParseInt(_)));
+    let err = CSV::from_path(temp_path("missing"), ',').unwrap_err();
+    assert!(matches!(err, BCSVError::Csv(_) | BCSVError::Io(_)));
+}
+
+#[test]
+fn from_reader_lays_out_like_recompute_layout() {
+    let text = "Id:0,ObjName:6,Scale:2,Count:4,Flag:5\n0,Kuribo,1,3,0\n1,Kinopio,0.5,-1,-1\n";
+    let csv = CSV::from_reader(text.as_bytes(), ',').unwrap();
+    let header = csv.header;
+    let fields = csv.fields.clone();
+    let mut bcsv = csv.create_bcsv();
+    assert_eq!((header.entrycount, header.entrysize, header.entrydataoff), (2, 15, 76));
+    bcsv.recompute_layout().unwrap();
+    assert_eq!(bcsv.header, header);
+    assert_eq!(bcsv.fields, fields);
+}
+
+#[test]
+fn float_format_applies_to_every_csv_path() {
+    let bcsv = BCSV::try_from("Scale:2\n0.1\n").unwrap();
+    let hashes = HashMap::from([(hash::calchash("Scale"), String::from("Scale"))]);
+    let text = bcsv.convert_to_csv(&hashes, true, ',');
+    assert_eq!(text, "Scale:2\n0.1\n");
+    let reread = BCSV::try_from(text.as_str()).unwrap();
+    assert_eq!(reread.get_by_name("Scale"), Some(&vec![Value::FLOAT(0.1)]));
+    let fixed = FloatFormat::Fixed(6);
+    let expected = "Scale:2\n0.100000\n";
+    assert_eq!(bcsv.convert_to_csv_with(&hashes, true, ',', fixed), expected);
+    assert_eq!(bcsv.convert_to_csv_columns_with(&bcsv.fields, &hashes, true, ',', fixed).unwrap(), expected);
+    let mut out = vec![];
+    bcsv.to_csv_writer_with(&mut out, &hashes, true, ',', fixed).unwrap();
+    assert_eq!(out, expected.as_bytes());
+    assert_eq!(bcsv.csv_lines_with(&hashes, true, ',', fixed).collect::<Vec<_>>(), ["Scale:2", "0.100000"]);
+    let mut writer = csv::Writer::from_writer(vec![]);
+    bcsv.write_csv_with(&mut writer, &hashes, true, fixed).unwrap();
+    assert_eq!(writer.into_inner().unwrap(), expected.as_bytes());
+    // Fixed rounds, so the text no longer parses back to the same bits.
+    let rounded = bcsv.convert_to_csv_with(&hashes, true, ',', FloatFormat::Fixed(0));
+    let reread = BCSV::try_from(rounded.as_str()).unwrap();
+    assert_eq!(reread.get_by_name("Scale"), Some(&vec![Value::FLOAT(0.0)]));
+}
+
+#[test]
+fn from_reader_keeps_float_and_string_fields_apart() {
+    let text = "A:1,B:1,C:2,D:2\nfoo,bar,1.5,2.5\n";
+    let bcsv = BCSV::try_from(text).unwrap();
+    assert_eq!(bcsv.header.entrysize, 72);
+    assert!(bcsv.verify_offsets());
+    let reread = BCSV::from_bytes(bcsv.to_bytes(libbcsv::Endian::Big).unwrap(), libbcsv::Endian::Big).unwrap();
+    let hashes = ["A", "B", "C", "D"].iter().map(|x| (hash::calchash(x), x.to_string())).collect();
+    assert_eq!(reread.convert_to_csv(&hashes, true, ','), text);
+}
@@ -0,0 +1,94 @@
+use libbcsv::hash;
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("libbcsv_{}_{}.txt", name, std::process::id()))
+}
+
+#[test]
+fn read_hashes_strips_trailing_comments() {
+    let path = temp_path("comments");
+    std::fs::write(&path, "# header comment\nFieldName # description\nOther\tName#1\n").unwrap();
+    let hashes = hash::read_hashes(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(hashes.get(&hash::calchash("FieldName")).map(String::as_str), Some("FieldName"));
+    assert_eq!(hashes.get(&hash::calchash("Other\tName#1")).map(String::as_str), Some("Other\tName#1"));
+    assert!(!hashes.contains_key(&hash::calchash("FieldName # description")));
+}
+
+#[test]
+fn read_hashes_reports_io_errors() {
+    let err = hash::read_hashes(temp_path("missing")).unwrap_err();
+    assert!(matches!(err, hash::HashError::Io(_)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn read_hashes_json_splits_names_and_types() {
+    use libbcsv::types::FieldType;
+    let path = temp_path("json");
+    std::fs::write(&path, r#"{"0x1234": {"name": "Raw", "type": "SHORT"}, "Scale": {"name": "Scale"}}"#).unwrap();
+    let (names, types) = hash::read_hashes_json(&path).unwrap();
+    assert_eq!(names.get(&0x1234).map(String::as_str), Some("Raw"));
+    assert_eq!(names.get(&hash::calchash("Scale")).map(String::as_str), Some("Scale"));
+    assert_eq!(types.get(&0x1234), Some(&FieldType::SHORT));
+    assert_eq!(types.len(), 1);
+    std::fs::write(&path, r#"{"0x1234": {"name": "Raw""#).unwrap();
+    assert!(matches!(hash::read_hashes_json(&path), Err(hash::HashError::Parse(_))));
+    std::fs::write(&path, r#"{"0xZZ": {"name": "Raw"}}"#).unwrap();
+    let err = hash::read_hashes_json(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(err.to_string().contains("\"0xZZ\" is not a valid hash"));
+}
+
+#[test]
+fn brute_force_recovers_short_names() {
+    let charset = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_";
+    let found = hash::brute_force(hash::calchash("Obj"), charset, 4).unwrap();
+    assert_eq!(hash::calchash(&found), hash::calchash("Obj"));
+    assert!(found.len() <= 3);
+    assert_eq!(hash::brute_force(hash::calchash("x"), b"ab", 3), None);
+    let targets = [hash::calchash("Id"), hash::calchash("Scale"), 1];
+    let names = hash::brute_force_many(&targets, b"IdScale", 5);
+    assert_eq!(names.get(&targets[0]).map(String::as_str), Some("Id"));
+    assert_eq!(names.get(&targets[1]).map(|x| hash::calchash(x)), Some(targets[1]));
+    assert!(!names.contains_key(&1));
+}
+
+#[test]
+fn byte_hashes_match_str_hashes() {
+    assert_eq!(hash::calchash_bytes(b"ObjName"), hash::calchash("ObjName"));
+    assert_eq!(hash::calc_old_hash_bytes(b"ObjName"), hash::calc_old_hash("ObjName"));
+    // "名前" in SHIFT_JIS, which isn't valid UTF-8.
+    let sjis = [0x96, 0xBC, 0x91, 0x4F];
+    assert!(String::from_utf8(sjis.to_vec()).is_err());
+    assert_eq!(hash::calchash_bytes(&sjis), ((0x96u32 * 31 + 0xBC) * 31 + 0x91) * 31 + 0x4F);
+}
+
+#[test]
+fn read_hashes_many_merges_files() {
+    let first = temp_path("many_first");
+    let second = temp_path("many_second");
+    std::fs::write(&first, "# game one\nObjName\nScale\n").unwrap();
+    std::fs::write(&second, "Scale # again\nFlag\n").unwrap();
+    let hashes = hash::read_hashes_many(&[&first, &second]).unwrap();
+    std::fs::remove_file(&first).unwrap();
+    std::fs::remove_file(&second).unwrap();
+    for name in ["ObjName", "Scale", "Flag"] {
+        assert_eq!(hashes.get(&hash::calchash(name)).map(String::as_str), Some(name));
+    }
+    assert!(matches!(hash::read_hashes_many(&[temp_path("many_missing")]), Err(hash::HashError::Io(_))));
+}
+
+#[test]
+fn rehash_list_picks_hash_function() {
+    let names = [String::from("Id"), String::from("ObjName")];
+    let new = hash::rehash_list(&names, false);
+    let old = hash::rehash_list(&names, true);
+    for name in &names {
+        assert_eq!(new.get(&hash::calchash(name)), Some(name));
+        assert_eq!(old.get(&hash::calc_old_hash(name)), Some(name));
+    }
+    assert_eq!((new.len(), old.len()), (2, 2));
+    assert_ne!(hash::calchash("ObjName"), hash::calc_old_hash("ObjName"));
+    assert!(!old.contains_key(&hash::calchash("ObjName")));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "serde")]
+use std::collections::HashMap;
+use libbcsv::{hash, types::*};
+
+#[test]
+fn field_round_trips_without_dataoff() {
+    let field = Field { hash: hash::calchash("Flags"), mask: 0xE0, dataoff: 12, shift: 5,
+        datatype: FieldType::SHORT as u8 };
+    let json = serde_json::to_string(&field).unwrap();
+    assert!(!json.contains("dataoff"));
+    let back: Field = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, Field { dataoff: 0, ..field });
+}
+
+#[test]
+fn field_deserializes_from_name() {
+    let field: Field = serde_json::from_str(r#"{"name": "Id", "type": "LONG"}"#).unwrap();
+    assert_eq!(field.hash, hash::calchash("Id"));
+    assert_eq!(field.mask, u32::MAX);
+    assert!(serde_json::from_str::<Field>(r#"{"type": "LONG"}"#).is_err());
+}
+
+#[test]
+fn header_round_trips() {
+    let header = Header { entrycount: 3, fieldcount: 5, entrydataoff: 76, entrysize: 15 };
+    let json = serde_json::to_string(&header).unwrap();
+    assert_eq!(serde_json::from_str::<Header>(&json).unwrap(), header);
+}
+
+#[test]
+fn bcsv_round_trips_through_json() {
+    let bcsv = BCSV::sample();
+    let hashes: HashMap<u32, String> = [(hash::calchash("Id"), String::from("Id"))].into();
+    let json = bcsv.to_json(&hashes).unwrap();
+    assert!(json.contains("\"Id\""));
+    assert_eq!(BCSV::from_json(&json).unwrap(), bcsv);
+}
+
+#[test]
+fn bcsv_from_json_lays_out_fields() {
+    let json = r#"{"fields": [{"name": "Id", "type": "LONG"}, {"name": "Name", "type": "STRINGOFF"}],
+        "rows": [{"Id": 1, "Name": "Kuribo"}, {"Id": -2, "Name": "Kinopio"}]}"#;
+    let bcsv = BCSV::from_json(json).unwrap();
+    assert!(bcsv.validate().is_ok());
+    assert_eq!(bcsv.header.entrycount, 2);
+    assert!(BCSV::from_json(r#"{"fields": [{"name": "Id", "type": "LONG"}], "rows": [{"Id": "x"}]}"#).is_err());
+}
@@ -0,0 +1,86 @@
+use libbcsv::{string_table::{read_string_table, StringTable}, types::{Value, BCSV}, Endian};
+
+#[test]
+fn build_bytes_matches_shift_jis_offsets() {
+    let mut table = StringTable::new();
+    table.push("クリボー").push("Kinopio").push("クリボー");
+    assert_eq!(table.find(&"Kinopio".into()), Some(&9));
+    let bytes = table.build_bytes().unwrap();
+    assert_eq!(bytes.len() as u32, table.byte_len());
+    assert_eq!(&bytes[..9], b"\x83\x4E\x83\x8A\x83\x7B\x81\x5B\0");
+    assert_eq!(&bytes[9..], b"Kinopio\0");
+}
+
+#[test]
+fn from_bytes_parses_existing_section() {
+    let data = b"\x83\x4E\x83\x8A\x83\x7B\x81\x5B\0Kinopio\0\0Kinopio\0@@@@";
+    let table = StringTable::from_bytes(data);
+    assert_eq!(table.find(&"クリボー".into()), Some(&0));
+    assert_eq!(table.find(&"Kinopio".into()), Some(&9));
+    assert_eq!(table.find(&"".into()), Some(&17));
+    assert_eq!(table.byte_len(), 26);
+    assert!(StringTable::from_bytes(b"@@@@").iter().next().is_none());
+}
+
+#[test]
+fn new_with_empty_keeps_empty_string_first() {
+    let mut table = StringTable::new_with_empty();
+    table.push("Kuribo").push("");
+    assert_eq!(table.find(&"".to_string()), Some(&0));
+    assert_eq!(table.find(&"Kuribo".into()), Some(&1));
+    let mut entries = vec![Value::STRINGOFF((5, String::new())), Value::STRINGOFF((0, "Kinopio".into()))];
+    table.update_offs(&mut entries);
+    assert!(matches!(&entries[0], Value::STRINGOFF((0, str)) if str.is_empty()));
+    assert_eq!(table.find(&"Kinopio".into()), Some(&8));
+}
+
+#[test]
+fn data_keeps_insertion_order() {
+    let mut table = StringTable::new();
+    for name in ["Kuribo", "Kinopio", "Kuribo", "Teresa", "Bom", "Kinopio"] {
+        table.push(name);
+    }
+    assert_eq!(table.data(), b"Kuribo\0Kinopio\0Teresa\0Bom\0");
+    let names: Vec<&str> = table.iter().map(|(x, _)| x).collect();
+    assert_eq!(names, ["Kuribo", "Kinopio", "Teresa", "Bom"]);
+}
+
+#[test]
+fn build_compact_shares_suffixes() {
+    let table = StringTable::build_compact(["LongName", "Name", "Other", "Name", "e"]);
+    assert_eq!(table.find(&"Name".into()), Some(&4));
+    assert_eq!(table.find(&"Other".into()), Some(&9));
+    assert_eq!(table.find(&"e".into()), Some(&7));
+    assert_eq!(table.byte_len(), 15);
+    assert_eq!(table.build_bytes().unwrap(), b"LongName\0Other\0");
+    assert_eq!(table.data(), b"LongName\0Other\0");
+    let mut plain = StringTable::new();
+    plain.push("LongName").push("Name");
+    assert_eq!(plain.find(&"Name".into()), Some(&9));
+}
+
+#[test]
+fn data_matches_shift_jis_offsets() {
+    let mut table = StringTable::new();
+    table.push("クリボー").push("K");
+    let data = table.data();
+    assert_eq!(table.find(&"K".into()), Some(&9));
+    assert_eq!(&data[9..], b"K\0");
+}
+
+#[test]
+fn read_string_table_stops_at_padding() {
+    let data = include_bytes!("fixtures/sample_be.bcsv");
+    let start = BCSV::from_bytes(data, Endian::Big).unwrap().header.stringoffset();
+    let len = data.len() as u64;
+    let read = |data: &[u8]| {
+        let mut reader = std::io::Cursor::new(data);
+        read_string_table(&mut reader, start, len, encoding_rs::SHIFT_JIS).collect::<Vec<_>>()
+    };
+    let expected = [(0, String::from("Kuribo")), (7, String::from("Kinopio"))];
+    assert_eq!(read(data), expected);
+    // Without its terminator the last string still stops before the padding.
+    let mut data = data.to_vec();
+    data[start as usize + 14] = 0x40;
+    assert_eq!(read(&data), expected);
+}
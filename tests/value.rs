@@ -0,0 +1,163 @@
+use libbcsv::types::*;
+
+fn packed_field(datatype: FieldType, mask: u32, shift: u8) -> Field {
+    Field { mask, shift, datatype: datatype as u8, ..Default::default() }
+}
+
+#[test]
+fn try_pack_shifts_into_mask() {
+    let field = packed_field(FieldType::SHORT, 0x0FF0, 4);
+    let packed = Value::SHORT(0xAB).try_pack(field).unwrap();
+    assert_eq!(packed.raw_bits(), 0x0AB0);
+}
+
+#[test]
+fn try_pack_rejects_values_too_large_for_mask() {
+    let field = packed_field(FieldType::CHAR, 0x70, 4);
+    assert!(Value::CHAR(7).try_pack(field).is_ok());
+    assert!(Value::CHAR(8).try_pack(field).is_err());
+    let field = packed_field(FieldType::LONG, u32::MAX, 32);
+    assert!(Value::LONG(1).try_pack(field).is_err());
+}
+
+#[test]
+fn with_bitfield_round_trips_packed_value() {
+    let field = Field::with_bitfield("Flags", FieldType::SHORT, 5, 3).unwrap();
+    assert_eq!((field.mask, field.shift), (0xE0, 5));
+    let packed = Value::SHORT(5).try_pack(field).unwrap();
+    assert_eq!(packed.raw_bits(), 5 << 5);
+    let low = Field::with_bitfield("Low", FieldType::SHORT, 0, 5).unwrap();
+    let mut bcsv = BCSV::new();
+    bcsv.fields = vec![low, field];
+    bcsv.header = Header { entrycount: 1, fieldcount: 2, entrydataoff: 16 + 12 * 2, entrysize: 2 };
+    bcsv.extend([(low, Value::SHORT(17)), (field, Value::SHORT(5))]);
+    let mut data = bcsv.to_bytes(binrw::Endian::Big).unwrap();
+    assert_eq!(data[40..42], [0x00, 5 << 5 | 17]);
+    let back = BCSV::from_bytes(&data, binrw::Endian::Big).unwrap();
+    assert_eq!(back.get_by_name("Flags"), Some(&vec![Value::SHORT(5)]));
+    assert_eq!(back.get_by_name("Low"), Some(&vec![Value::SHORT(17)]));
+    // Set every bit outside both fields to check they're masked off on read.
+    data[40] = 0xFF;
+    let back = BCSV::from_bytes(&data, binrw::Endian::Big).unwrap();
+    assert_eq!(back.get_by_name("Flags"), Some(&vec![Value::SHORT(5)]));
+    assert!(Field::with_bitfield("Flags", FieldType::SHORT, 14, 3).is_err());
+    assert!(Field::with_bitfield("Flags", FieldType::FLOAT, 0, 3).is_err());
+}
+
+#[test]
+fn read_ignores_reader_position() {
+    let data = include_bytes!("fixtures/sample_be.bcsv");
+    let header = Header { entrycount: 3, fieldcount: 5, entrydataoff: 76, entrysize: 15 };
+    let field = Field { hash: libbcsv::hash::calchash("Count"), mask: 0xFFFF, dataoff: 12,
+        datatype: FieldType::SHORT as u8, ..Default::default() };
+    let mut reader = std::io::Cursor::new(&data[..]);
+    reader.set_position(data.len() as u64);
+    let mut value = Value::SHORT(0);
+    value.read(&mut reader, binrw::Endian::Big, 1, header, field).unwrap();
+    assert_eq!(value.get_string(true), "-1");
+    assert_eq!(reader.position(), data.len() as u64);
+}
+
+#[test]
+fn data_size_distinguishes_inline_strings() {
+    assert_eq!(packed_field(FieldType::STRING, 0, 0).data_size(), 32);
+    assert_eq!(packed_field(FieldType::STRINGOFF, u32::MAX, 0).data_size(), 4);
+}
+
+#[test]
+fn from_hash_uses_type_mask() {
+    let field = Field::from_hash(0x21738C, FieldType::CHAR);
+    assert_eq!(field, Field { hash: 0x21738C, mask: 0xFF, datatype: FieldType::CHAR as u8, ..Default::default() });
+}
+
+#[test]
+fn stringoff_equality_ignores_offset() {
+    let a = Value::STRINGOFF((0, "Kuribo".into()));
+    let b = Value::STRINGOFF((7, "Kuribo".into()));
+    assert_eq!(a, b);
+    assert_ne!(a, Value::STRINGOFF((0, "Kinopio".into())));
+}
+
+#[test]
+fn typed_accessors_widen_losslessly() {
+    assert_eq!(Value::SHORT(0xFFFF).as_i32(), Some(-1));
+    assert_eq!(Value::SHORT(0xFFFF).as_u32(), Some(0xFFFF));
+    assert_eq!(Value::CHAR(0x80).as_i16(), Some(-128));
+    assert_eq!(Value::CHAR(0x80).as_i8(), Some(-128));
+    assert_eq!(Value::LONG(-5).as_i32(), Some(-5));
+    assert_eq!(Value::LONG(-5).as_u32(), None);
+    assert_eq!(Value::FLOAT(0.5).as_f32(), Some(0.5));
+    assert_eq!(Value::STRINGOFF((3, "Kuribo".into())).as_str(), Some("Kuribo"));
+    let mut name = [0u8; 32];
+    name[..6].copy_from_slice(b"Kuribo");
+    assert_eq!(Value::STRING(name).as_str(), Some("Kuribo"));
+    assert_eq!(Value::ULONG(1).as_str(), None);
+}
+
+#[test]
+fn field_type_parses_names_and_numbers() {
+    for datatype in [FieldType::LONG, FieldType::STRING, FieldType::FLOAT, FieldType::ULONG,
+        FieldType::SHORT, FieldType::CHAR, FieldType::STRINGOFF, FieldType::NULL] {
+        assert_eq!(datatype.to_string().parse::<FieldType>().unwrap(), datatype);
+        assert_eq!((datatype as u8).to_string().parse::<FieldType>().unwrap(), datatype);
+    }
+    assert_eq!(FieldType::STRINGOFF.to_string(), "STRINGOFF");
+    assert!("8".parse::<FieldType>().is_err());
+    assert!("long".parse::<FieldType>().is_err());
+}
+
+#[test]
+fn value_range_covers_each_integer_type() {
+    for signed in [true, false] {
+        assert_eq!(FieldType::LONG.value_range(signed), Some((-2147483648, 2147483647)));
+        assert_eq!(FieldType::ULONG.value_range(signed), Some((0, 4294967295)));
+        for datatype in [FieldType::FLOAT, FieldType::STRING, FieldType::STRINGOFF, FieldType::NULL] {
+            assert_eq!(datatype.value_range(signed), None);
+        }
+    }
+    assert_eq!(FieldType::SHORT.value_range(true), Some((-32768, 32767)));
+    assert_eq!(FieldType::SHORT.value_range(false), Some((0, 65535)));
+    assert_eq!(FieldType::CHAR.value_range(true), Some((-128, 127)));
+    assert_eq!(FieldType::CHAR.value_range(false), Some((0, 255)));
+}
+
+#[test]
+fn float_equality_compares_bits() {
+    assert_eq!(Value::FLOAT(f32::NAN), Value::FLOAT(f32::NAN));
+    assert_ne!(Value::FLOAT(0.0), Value::FLOAT(-0.0));
+    assert_ne!(Value::LONG(1), Value::ULONG(1));
+    assert_eq!(Value::NULL, Value::NULL);
+}
+
+#[test]
+fn normalize_string_zeroes_after_terminator() {
+    let mut raw = [b'x'; 32];
+    raw[3] = 0;
+    let mut value = Value::STRING(raw);
+    value.normalize_string();
+    let mut expected = [0u8; 32];
+    expected[..3].copy_from_slice(b"xxx");
+    assert!(matches!(value, Value::STRING(s) if s == expected));
+    // Without a terminator all 32 bytes are text and are kept.
+    let mut value = Value::STRING([b'y'; 32]);
+    value.normalize_string();
+    assert!(matches!(value, Value::STRING(s) if s == [b'y'; 32]));
+    let mut value = Value::LONG(5);
+    value.normalize_string();
+    assert_eq!(value, Value::LONG(5));
+}
+
+#[test]
+fn string_round_trips_without_trailing_nulls() {
+    let mut raw = [0u8; 32];
+    raw[..3].copy_from_slice(b"Foo");
+    raw[10] = b'x';
+    let value = Value::STRING(raw);
+    assert_eq!(value.get_string(false), "Foo");
+    let mut out = std::io::Cursor::new(vec![]);
+    value.write(&mut out, binrw::Endian::Big).unwrap();
+    let out = out.into_inner();
+    assert_eq!(out.len(), 32);
+    assert_eq!(&out[..3], b"Foo");
+    assert!(out[3..].iter().all(|x| *x == 0));
+}